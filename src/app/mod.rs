@@ -1,9 +1,5 @@
-use reqwest::{
-    Client
-};
 use pocket_api_client::{
-    PocketApiTokenReceiver,
-    PocketApiConfig
+    PocketApiTokenReceiver
 };
 use crate::{
     pub_sub::{
@@ -11,19 +7,80 @@ use crate::{
     },
     telegram_client::{
         TelegramClient,
-        TelegramUserId
+        TelegramChatId,
+        TelegramBotIdentity
     },
     redis_storrage::{
         RedisStorrage
+    },
+    pocket_items::{
+        PocketItemsClient
+    },
+    telegram_handlers::{
+        UserEvent
     }
 };
 
 pub struct Application{
-    pub http_client: Client,
     pub telegram_client: TelegramClient,
-    pub telegram_bot_url: url::Url,
+    pub telegram_bot: TelegramBotIdentity,
+    /// Секрет для проверки входящих webhook запросов, если включен такой режим
+    pub telegram_webhook_secret: Option<String>,
     pub redis_client: RedisStorrage,
-    pub active_processors: PubSub<TelegramUserId, String>,
-    pub pocket_api_config: PocketApiConfig,
-    pub pocket_token_receiver: PocketApiTokenReceiver
-}
\ No newline at end of file
+    /// Персональные обработчики по идентификатору чата
+    pub active_processors: PubSub<TelegramChatId, UserEvent>,
+    pub pocket_token_receiver: PocketApiTokenReceiver,
+    pub pocket_items: PocketItemsClient
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod fakes {
+    use std::{
+        sync::{
+            Arc
+        }
+    };
+    use pocket_api_client::{
+        PocketApiConfig
+    };
+    use reqwest::{
+        Client
+    };
+    use url::{
+        Url
+    };
+    use crate::{
+        telegram_client::{
+            TelegramFakeTransport
+        },
+        redis_storrage::{
+            MemoryStorageBackend
+        },
+        pocket_items::{
+            PocketFakeTransport
+        }
+    };
+    use super::*;
+
+    impl Application {
+        /// Приложение без сети и Redis: Telegram и Pocket отвечают из фейков, данные лежат в памяти
+        pub fn with_fakes(telegram: Arc<TelegramFakeTransport>, pocket: Arc<PocketFakeTransport>) -> Application {
+            let pocket_api_config = PocketApiConfig::new_default(Client::new(), "consumer_key".to_string());
+            let redirect_url = Url::parse("http://localhost:8080/pocket_auth_callback").expect("Invalid redirect url");
+            Application{
+                telegram_client: TelegramClient::with_transport(telegram),
+                telegram_bot: TelegramBotIdentity{
+                    username: "pocket_test_bot".to_string(),
+                    url: Url::parse("https://t.me/pocket_test_bot").expect("Invalid bot url")
+                },
+                telegram_webhook_secret: None,
+                redis_client: RedisStorrage::with_backend(Arc::new(MemoryStorageBackend::default())),
+                active_processors: Default::default(),
+                pocket_token_receiver: PocketApiTokenReceiver::new(pocket_api_config, redirect_url),
+                pocket_items: PocketItemsClient::with_transport(pocket)
+            }
+        }
+    }
+}
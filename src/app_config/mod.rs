@@ -1,12 +1,69 @@
 
+/// Способ получения обновлений от Telegram
+#[derive(Debug)]
+pub enum TelegramUpdatesMode{
+    /// Постоянные запросы getUpdates
+    LongPolling,
+
+    /// Telegram сам присылает обновления на наш веб-сервер
+    Webhook{
+        url: url::Url,
+        secret_token: String
+    }
+}
+
+/// Адрес вида `<base><prefix><token>/`. Собираем строкой, а не через `Url::join`:
+/// токен содержит `:`, и join принял бы `bot123:ABC/` за адрес со схемой `bot123`.
+fn telegram_token_url(base: &url::Url, prefix: &str, token: &str) -> Result<url::Url, url::ParseError> {
+    url::Url::parse(&format!("{}{}{}/", base.as_str(), prefix, token))
+}
+
+/// Запись и воспроизведение обмена с Telegram, только для отладки и тестов
+#[cfg(feature = "telegram_fixtures")]
+#[derive(Debug, Default)]
+pub struct TelegramFixturesConfig{
+    /// Файл, в который записывается обмен с Telegram
+    pub record: Option<std::path::PathBuf>,
+    /// Файл с записанным обменом, ответы Telegram берутся из него вместо сети
+    pub replay: Option<std::path::PathBuf>,
+    /// При воспроизведении подбирать ответы только по имени метода
+    pub replay_ignore_params: bool
+}
+
+#[cfg(feature = "telegram_fixtures")]
+impl TelegramFixturesConfig{
+    fn parse_from_env() -> TelegramFixturesConfig{
+        let record = std::env::var("TELEGRAM_RECORD_FIXTURE")
+            .ok()
+            .map(std::path::PathBuf::from);
+        let replay = std::env::var("TELEGRAM_REPLAY_FIXTURE")
+            .ok()
+            .map(std::path::PathBuf::from);
+        assert!(record.is_none() || replay.is_none(), 
+                "TELEGRAM_RECORD_FIXTURE and TELEGRAM_REPLAY_FIXTURE can't be used together");
+        let replay_ignore_params = std::env::var("TELEGRAM_REPLAY_IGNORE_PARAMS")
+            .map(|value| value == "1" || value == "true")
+            .unwrap_or(false);
+        TelegramFixturesConfig{
+            record,
+            replay,
+            replay_ignore_params
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TelegramBotConfig{
     pub telegram_bot_token: String,
-    pub telegram_bot_url: url::Url,
     pub pocket_consumer_key: String,
     pub pocket_redirect_web_server_port: u16,
     pub pocket_redirect_uri: url::Url,
-    pub redis_address: String
+    pub redis_address: String,
+    pub telegram_updates_mode: TelegramUpdatesMode,
+    /// Адрес Bot API сервера, официального или своего `telegram-bot-api`
+    pub telegram_api_base_url: url::Url,
+    #[cfg(feature = "telegram_fixtures")]
+    pub telegram_fixtures: TelegramFixturesConfig
 }
 
 impl TelegramBotConfig{
@@ -23,20 +80,122 @@ impl TelegramBotConfig{
             .expect("POCKET_REDIRECT_WEB_SERVER_PORT is invalid port value");
         let telegram_bot_token = std::env::var("TELEGRAM_BOT_TOKEN")
             .expect("TELEGRAM_BOT_TOKEN env var is missing");
-        let telegram_bot_url = std::env::var("TELEGRAM_BOT_URL")
-            .expect("TELEGRAM_BOT_URL env var is missing")
-            .parse()
-            .expect("TELEGRAM_BOT_URL is invalid URL");
         let redis_address = std::env::var("REDIS_ADDRESS")
             .expect("REDIS_ADDRESS env var is missing");
+        let telegram_api_base_url = {
+            let mut url = std::env::var("TELEGRAM_API_BASE_URL")
+                .ok()
+                .unwrap_or_else(|| "https://api.telegram.org/".to_string());
+            // Без завершающего `/` join заменит последний сегмент пути
+            if !url.ends_with('/') {
+                url.push('/');
+            }
+            url.parse()
+                .expect("TELEGRAM_API_BASE_URL is invalid URL")
+        };
+        let telegram_updates_mode = match std::env::var("TELEGRAM_UPDATES_MODE").ok().as_deref() {
+            None | Some("long_polling") => {
+                TelegramUpdatesMode::LongPolling
+            },
+            Some("webhook") => {
+                let url = std::env::var("TELEGRAM_WEBHOOK_URL")
+                    .expect("TELEGRAM_WEBHOOK_URL env var is missing")
+                    .parse()
+                    .expect("TELEGRAM_WEBHOOK_URL is invalid URL");
+                let secret_token = std::env::var("TELEGRAM_WEBHOOK_SECRET")
+                    .expect("TELEGRAM_WEBHOOK_SECRET env var is missing");
+                // Telegram допускает только такие символы в секрете
+                assert!(!secret_token.is_empty() && secret_token.len() <= 256 &&
+                        secret_token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+                        "TELEGRAM_WEBHOOK_SECRET must be 1-256 characters of A-Z, a-z, 0-9, _ and -");
+                TelegramUpdatesMode::Webhook{
+                    url,
+                    secret_token
+                }
+            },
+            Some(other) => {
+                panic!("TELEGRAM_UPDATES_MODE has invalid value: {}, expected long_polling or webhook", other);
+            }
+        };
+        #[cfg(feature = "telegram_fixtures")]
+        let telegram_fixtures = {
+            let fixtures = TelegramFixturesConfig::parse_from_env();
+            // Через webhook приходили бы настоящие обновления, а ответы на них брались бы из записи
+            assert!(fixtures.replay.is_none() || matches!(telegram_updates_mode, TelegramUpdatesMode::LongPolling),
+                    "TELEGRAM_REPLAY_FIXTURE can't be used with webhook updates mode");
+            fixtures
+        };
 
         TelegramBotConfig{
             pocket_consumer_key,
             pocket_redirect_web_server_port,
             pocket_redirect_uri,
             telegram_bot_token,
-            telegram_bot_url,
-            redis_address
+            redis_address,
+            telegram_updates_mode,
+            telegram_api_base_url,
+            #[cfg(feature = "telegram_fixtures")]
+            telegram_fixtures
         }
     }
-}
\ No newline at end of file
+
+    /// Адрес для вызова методов вида `<base>/bot<token>/`
+    pub fn telegram_api_url(&self) -> url::Url {
+        telegram_token_url(&self.telegram_api_base_url, "bot", &self.telegram_bot_token)
+            .expect("Invalid telegram api url")
+    }
+
+    /// Адрес для скачивания файлов вида `<base>/file/bot<token>/`
+    pub fn telegram_file_url(&self) -> url::Url {
+        telegram_token_url(&self.telegram_api_base_url, "file/bot", &self.telegram_bot_token)
+            .expect("Invalid telegram file url")
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_TOKEN: &str = "123456789:ABCdefGhIJKlmNoPQRsTUVwxyZ";
+
+    fn test_config(api_base_url: &str) -> TelegramBotConfig {
+        TelegramBotConfig{
+            telegram_bot_token: TEST_TOKEN.to_string(),
+            pocket_consumer_key: String::new(),
+            pocket_redirect_web_server_port: 8080,
+            pocket_redirect_uri: "http://localhost:8080/pocket_auth_callback".parse().unwrap(),
+            redis_address: String::new(),
+            telegram_updates_mode: TelegramUpdatesMode::LongPolling,
+            telegram_api_base_url: api_base_url.parse().unwrap(),
+            #[cfg(feature = "telegram_fixtures")]
+            telegram_fixtures: Default::default()
+        }
+    }
+
+    #[test]
+    fn test_api_url_with_default_base() {
+        let url = test_config("https://api.telegram.org/").telegram_api_url();
+        assert_eq!(url.as_str(), "https://api.telegram.org/bot123456789:ABCdefGhIJKlmNoPQRsTUVwxyZ/");
+        assert_eq!(url.join("getMe").unwrap().as_str(),
+                   "https://api.telegram.org/bot123456789:ABCdefGhIJKlmNoPQRsTUVwxyZ/getMe");
+    }
+
+    #[test]
+    fn test_api_url_with_custom_base() {
+        let url = test_config("http://localhost:8081/telegram/").telegram_api_url();
+        assert_eq!(url.as_str(), "http://localhost:8081/telegram/bot123456789:ABCdefGhIJKlmNoPQRsTUVwxyZ/");
+    }
+
+    #[test]
+    fn test_file_url_with_default_base() {
+        let url = test_config("https://api.telegram.org/").telegram_file_url();
+        assert_eq!(url.as_str(), "https://api.telegram.org/file/bot123456789:ABCdefGhIJKlmNoPQRsTUVwxyZ/");
+        assert_eq!(url.join("documents/file_1.txt").unwrap().as_str(),
+                   "https://api.telegram.org/file/bot123456789:ABCdefGhIJKlmNoPQRsTUVwxyZ/documents/file_1.txt");
+    }
+
+    #[test]
+    fn test_file_url_with_custom_base() {
+        let url = test_config("http://localhost:8081/telegram/").telegram_file_url();
+        assert_eq!(url.as_str(), "http://localhost:8081/telegram/file/bot123456789:ABCdefGhIJKlmNoPQRsTUVwxyZ/");
+    }
+}
@@ -21,6 +21,10 @@ quick_error!{
             from()
         }
 
+        IoError(err: std::io::Error){
+            from()
+        }
+
         JsonParseError(err: serde_json::Error){
             from()
         }
@@ -40,6 +44,30 @@ quick_error!{
         PocketError(err: pocket_api_client::PocketApiError){
             from()
         }
+
+        /// Действие требует подключенного аккаунта Pocket
+        NotAuthorized{
+        }
+
+        /// Пустой текст, отправлять нечего
+        EmptyMessageError{
+        }
+
+        /// Ошибка транспорта без сети: нет ответа в фейке или записи
+        TransportError(description: String){
+        }
+
+        /// Файл больше допустимого размера в байтах
+        FileTooLarge(limit: u64){
+        }
+
+        /// Telegram не отдал путь к файлу, скачать его нельзя
+        FileIsNotAvailable{
+        }
+
+        /// Ошибка от Pocket API при работе с элементами: статус, X-Error-Code и X-Error
+        PocketItemsError(status: reqwest::StatusCode, code: Option<i32>, message: Option<String>){
+        }
     }
 }
 
@@ -1,5 +1,9 @@
 use serde::{
-    Deserialize
+    Deserialize,
+    Deserializer
+};
+use serde_json::{
+    Value
 };
 
 ////////////////////////////////////////////////////////////////////////
@@ -20,3 +24,61 @@ impl<D, E> DataOrErrorResponse<D, E> {
         }
     }
 }
+
+////////////////////////////////////////////////////////////////////////
+
+/// Pocket отдает числа то строками, то числами, поэтому парсим оба варианта
+pub fn deserialize_optional_number<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    let value: Option<Value> = Option::deserialize(deserializer)?;
+    let number = match value {
+        Some(Value::Number(n)) => n.as_i64(),
+        Some(Value::String(s)) => s.parse().ok(),
+        _ => None
+    };
+    Ok(number)
+}
+
+/// Идентификаторы в Pocket обычно строки, но иногда приходят числами
+pub fn deserialize_optional_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    let value: Option<Value> = Option::deserialize(deserializer)?;
+    let text = match value {
+        Some(Value::Number(n)) => Some(n.to_string()),
+        Some(Value::String(s)) => Some(s),
+        _ => None
+    };
+    Ok(text)
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Сравнение секретов за время, не зависящее от позиции первого отличия.
+/// Длина при этом не скрывается, но она и так известна из формата секрета.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left
+        .iter()
+        .zip(right.iter())
+        .fold(0u8, |diff, (l, r)| diff | (l ^ r)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret_token", b"secret_token"));
+        assert!(constant_time_eq(b"", b""));
+        assert!(!constant_time_eq(b"secret_token", b"secret_tokem"));
+        assert!(!constant_time_eq(b"secret_token", b"Secret_token"));
+        assert!(!constant_time_eq(b"secret_token", b"secret"));
+    }
+}
@@ -7,6 +7,7 @@ mod model;
 mod telegram_handlers;
 mod telegram_client;
 mod redis_storrage;
+mod pocket_items;
 mod web_server;
 
 use std::{
@@ -16,6 +17,7 @@ use std::{
 };
 use tracing::{
     debug,
+    warn,
     error
 };
 use tracing_subscriber::{
@@ -37,19 +39,31 @@ use pocket_api_client::{
 };
 use crate::{
     app_config::{
-        TelegramBotConfig
+        TelegramBotConfig,
+        TelegramUpdatesMode
     },
     app::{
         Application
     },
     telegram_handlers::{
-        telegram_receive_updates_loop
+        telegram_receive_updates_loop,
+        register_bot_commands,
+        TELEGRAM_ALLOWED_UPDATES
     },
     telegram_client::{
-        TelegramClient
+        TelegramClient,
+        TelegramBotIdentity,
+        TelegramTransport,
+        TelegramHttpTransport
+    },
+    error::{
+        TelegramBotError
     },
     redis_storrage::{
         RedisStorrage
+    },
+    pocket_items::{
+        PocketItemsClient
     }
 };
 
@@ -78,6 +92,30 @@ fn initialize_logs() {
     tracing::subscriber::set_global_default(full_subscriber).unwrap();    
 }
 
+/// Запись обмена с Telegram или воспроизведение ранее записанного вместо сети
+#[cfg(feature = "telegram_fixtures")]
+fn with_telegram_fixtures(config: &TelegramBotConfig, transport: Arc<dyn TelegramTransport>) -> Arc<dyn TelegramTransport> {
+    use crate::telegram_client::TelegramRecordReplayTransport;
+
+    let fixtures = &config.telegram_fixtures;
+    if let Some(path) = &fixtures.replay {
+        warn!("Telegram responses are replayed from {:?}", path);
+        let replay = TelegramRecordReplayTransport::replay(path.clone())
+            .expect("Telegram replay fixture load failed");
+        if fixtures.replay_ignore_params {
+            Arc::new(replay.ignore_params())
+        }else{
+            Arc::new(replay)
+        }
+    }else if let Some(path) = &fixtures.record {
+        warn!("Telegram exchange is recorded to {:?}", path);
+        Arc::new(TelegramRecordReplayTransport::record(transport, path.clone())
+            .expect("Telegram record fixture create failed"))
+    }else{
+        transport
+    }
+}
+
 #[tokio::main]
 async fn main(){
     dotenv::from_path("env/local.env").ok();
@@ -90,10 +128,34 @@ async fn main(){
     let http_client = Client::new();
 
     let telegram_client = {
-        let telegram_bot_api_url = url::Url::parse(&format!("https://api.telegram.org/bot{}/", config.telegram_bot_token))
-            .expect("Invalid telegram api url");
-        TelegramClient::new(http_client.clone(), telegram_bot_api_url)
+        let transport: Arc<dyn TelegramTransport> = Arc::new(TelegramHttpTransport::new(http_client.clone(), 
+                                                                                        config.telegram_api_url(), 
+                                                                                        config.telegram_file_url()));
+        #[cfg(feature = "telegram_fixtures")]
+        let transport = with_telegram_fixtures(&config, transport);
+        TelegramClient::with_transport(transport)
+    };
+
+    // Проверяем токен и узнаем имя бота, ссылки на бота строятся из него
+    let telegram_bot = match telegram_client.get_me().await {
+        Ok(me) => {
+            // Без этих настроек в BotFather часть функций просто не будет получать обновления
+            if me.supports_inline_queries != Some(true) {
+                warn!("Inline mode is disabled for the bot, inline queries won't be received");
+            }
+            if me.can_join_groups == Some(false) {
+                warn!("Bot can't be added to groups");
+            }
+            TelegramBotIdentity::new(me)
+        },
+        Err(TelegramBotError::ApiError(err)) if err.error_code == 401 || err.error_code == 404 => {
+            panic!("TELEGRAM_BOT_TOKEN is rejected by Telegram: {}", err.description);
+        },
+        Err(err) => {
+            panic!("Telegram getMe request failed: {}", err);
+        }
     };
+    debug!("Telegram bot: {:?}", telegram_bot);
 
     let redis_client = {
         let redis_manager = bb8_redis::RedisConnectionManager::new(config.redis_address.clone())
@@ -121,28 +183,63 @@ async fn main(){
         RedisStorrage::new(pool)
     };
 
+    let pocket_items = PocketItemsClient::new(http_client.clone(), config.pocket_consumer_key.clone());
     let pocket_api_config = PocketApiConfig::new_default(http_client.clone(), config.pocket_consumer_key);
-    let pocket_token_receiver = PocketApiTokenReceiver::new(pocket_api_config.clone(), 
+    let pocket_token_receiver = PocketApiTokenReceiver::new(pocket_api_config, 
                                                             config.pocket_redirect_uri);
 
 
+    let telegram_webhook_secret = match &config.telegram_updates_mode {
+        TelegramUpdatesMode::Webhook{secret_token, ..} => Some(secret_token.clone()),
+        TelegramUpdatesMode::LongPolling => None
+    };
+
     let app = Arc::new(Application{
-        http_client,
         telegram_client,
-        telegram_bot_url: config.telegram_bot_url,
+        telegram_bot,
+        telegram_webhook_secret,
         redis_client,
         active_processors: Default::default(),
-        pocket_api_config,
-        pocket_token_receiver
+        pocket_token_receiver,
+        pocket_items
     });
 
+    // Меню команд не критично для работы, поэтому только логируем ошибку
+    if let Err(err) = register_bot_commands(&app.telegram_client).await {
+        error!("Bot commands register error: {}", err);
+    }
+
     // TODO: Gracefull shutdown
-    tokio::spawn(web_server::run_server(app.clone(), config.pocket_redirect_web_server_port));
+    let web_server = tokio::spawn(web_server::run_server(app.clone(), config.pocket_redirect_web_server_port));
+
+    match config.telegram_updates_mode {
+        TelegramUpdatesMode::Webhook{url, secret_token} => {
+            // Сервер уже запущен, можно регистрировать webhook, обновления пойдут через него
+            app
+                .telegram_client
+                .set_webhook(url, secret_token, TELEGRAM_ALLOWED_UPDATES)
+                .await
+                .expect("Telegram webhook register failed");
+            debug!("Telegram webhook registered");
 
-    loop {
-        if let Err(err) = telegram_receive_updates_loop(app.clone()).await {
-            error!("Updates receive error: {}", err);
-            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            web_server
+                .await
+                .expect("Web server failed");
+        },
+        TelegramUpdatesMode::LongPolling => {
+            // При активном webhook запросы getUpdates не работают
+            app
+                .telegram_client
+                .delete_webhook()
+                .await
+                .expect("Telegram webhook delete failed");
+
+            loop {
+                if let Err(err) = telegram_receive_updates_loop(app.clone()).await {
+                    error!("Updates receive error: {}", err);
+                    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+                }
+            }
         }
     }
 }
\ No newline at end of file
@@ -1,3 +1,9 @@
+use std::{
+    time::{
+        SystemTime,
+        UNIX_EPOCH
+    }
+};
 use serde::{
     Serialize,
     Deserialize
@@ -5,7 +11,7 @@ use serde::{
 use crate::{
     telegram_client::{
         TelegramMessageId,
-        TelegramUserId
+        TelegramChatId
     }
 };
 
@@ -15,11 +21,177 @@ pub enum UserState {
     Unauthorized,
     AutorizationConfirmationWaiting{
         telegram_message_id: TelegramMessageId,
-        telegram_user_id: TelegramUserId,
+        /// Чат, в который отправлена ссылка на авторизацию, в личных чатах совпадает с пользователем.
+        /// Раньше поле называлось `telegram_user_id`, старое имя читаем из уже сохраненных состояний.
+        #[serde(alias = "telegram_user_id")]
+        telegram_chat_id: TelegramChatId,
         pocket_auth_url: String,
         pocket_auth_code: String
     },
     Authorized{
         pocket_api_token: String,
     }
-}
\ No newline at end of file
+}
+
+/// Состояние без данных, чтобы можно было описывать в каких состояниях доступна команда
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserStateKind {
+    Unauthorized,
+    AutorizationConfirmationWaiting,
+    Authorized
+}
+
+impl UserState {
+    pub fn kind(&self) -> UserStateKind {
+        match self {
+            UserState::Unauthorized => UserStateKind::Unauthorized,
+            UserState::AutorizationConfirmationWaiting{..} => UserStateKind::AutorizationConfirmationWaiting,
+            UserState::Authorized{..} => UserStateKind::Authorized
+        }
+    }
+}
+
+/// Номер текущего дня по UTC, нужен для сброса дневной статистики
+fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() / (60 * 60 * 24))
+        .unwrap_or(0)
+}
+
+/// Сколько последних неудачных ссылок храним, их же показывает закрепленное сообщение
+pub const FAILED_SAVES_LIMIT: usize = 5;
+
+/// Статистика сохранений для закрепленного сообщения со статусом
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UserStats {
+    /// День, к которому относится `saved_today`
+    pub day: u64,
+    pub saved_today: u32,
+    /// Сколько раз сегодня делились сохраненным через inline режим
+    #[serde(default)]
+    pub shared_today: u32,
+    pub last_saved_title: Option<String>,
+    /// Последние ссылки, которые не удалось сохранить, не больше `FAILED_SAVES_LIMIT`
+    pub failed_saves: Vec<String>
+}
+
+impl UserStats {
+    /// Со сменой дня счетчик начинается заново
+    fn roll_day(&mut self) {
+        let today = current_day();
+        if self.day != today {
+            self.day = today;
+            self.saved_today = 0;
+            self.shared_today = 0;
+        }
+    }
+
+    pub fn saved_today(&self) -> u32 {
+        if self.day == current_day() {
+            self.saved_today
+        }else{
+            0
+        }
+    }
+
+    pub fn shared_today(&self) -> u32 {
+        if self.day == current_day() {
+            self.shared_today
+        }else{
+            0
+        }
+    }
+
+    pub fn record_saved(&mut self, link: &str, title: Option<&str>) {
+        self.roll_day();
+        self.saved_today += 1;
+        if let Some(title) = title {
+            self.last_saved_title = Some(title.to_string());
+        }
+        self.failed_saves.retain(|failed| failed != link);
+    }
+
+    pub fn record_imported(&mut self, count: u32) {
+        self.roll_day();
+        self.saved_today += count;
+    }
+
+    pub fn record_shared(&mut self) {
+        self.roll_day();
+        self.shared_today += 1;
+    }
+
+    pub fn record_failed(&mut self, link: &str) {
+        self.failed_saves.retain(|failed| failed != link);
+        self.failed_saves.push(link.to_string());
+        // Старые ошибки вытесняются новыми, иначе список рос бы бесконечно
+        if self.failed_saves.len() > FAILED_SAVES_LIMIT {
+            let extra = self.failed_saves.len() - FAILED_SAVES_LIMIT;
+            self.failed_saves.drain(..extra);
+        }
+    }
+}
+
+/// Результаты `/search` в конкретном сообщении: аргументы команды и открытая страница,
+/// чтобы кнопки могли перелистывать и обновлять результаты
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchSession {
+    pub args: String,
+    pub page: usize
+}
+
+/// Ссылка из подтверждения сохранения: `item_id` из Pocket и состояние для кнопок под сообщением
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedItem {
+    pub item_id: String,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub favorite: bool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_saves_are_capped() {
+        let mut stats = UserStats::default();
+        for index in 0..FAILED_SAVES_LIMIT + 3 {
+            stats.record_failed(&format!("https://example.com/{}", index));
+        }
+        assert_eq!(stats.failed_saves.len(), FAILED_SAVES_LIMIT);
+        assert_eq!(stats.failed_saves.first().unwrap(), "https://example.com/3");
+        assert_eq!(stats.failed_saves.last().unwrap(), &format!("https://example.com/{}", FAILED_SAVES_LIMIT + 2));
+
+        // Повторная ошибка переносит ссылку в конец без дубликатов
+        stats.record_failed("https://example.com/3");
+        assert_eq!(stats.failed_saves.len(), FAILED_SAVES_LIMIT);
+        assert_eq!(stats.failed_saves.last().unwrap(), "https://example.com/3");
+
+        // Успешное сохранение убирает ссылку из ошибок
+        stats.record_saved("https://example.com/3", None);
+        assert!(!stats.failed_saves.iter().any(|link| link == "https://example.com/3"));
+    }
+
+    #[test]
+    fn test_confirmation_waiting_state_with_old_field_name() {
+        let json = r#"{
+            "type": "AutorizationConfirmationWaiting",
+            "content": {
+                "telegram_message_id": 10,
+                "telegram_user_id": 123,
+                "pocket_auth_url": "https://getpocket.com/auth/authorize",
+                "pocket_auth_code": "code"
+            }
+        }"#;
+        match serde_json::from_str::<UserState>(json).unwrap() {
+            UserState::AutorizationConfirmationWaiting{telegram_chat_id, telegram_message_id, ..} => {
+                assert_eq!(telegram_chat_id, 123);
+                assert_eq!(telegram_message_id, 10);
+            },
+            state => panic!("Unexpected state: {:?}", state)
+        }
+    }
+}
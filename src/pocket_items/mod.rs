@@ -0,0 +1,342 @@
+mod transport;
+
+use std::{
+    sync::{
+        Arc
+    }
+};
+use reqwest::{
+    Client
+};
+use url::{
+    Url
+};
+use serde::{
+    de::{
+        DeserializeOwned
+    },
+    Serialize,
+    Deserialize,
+    Deserializer
+};
+use serde_json::{
+    json,
+    Value
+};
+use tracing::{
+    instrument
+};
+use crate::{
+    error::{
+        TelegramBotError
+    },
+    helpers::{
+        deserialize_optional_number,
+        deserialize_optional_string
+    }
+};
+
+pub use self::{
+    transport::{
+        PocketTransport,
+        PocketHttpTransport
+    }
+};
+#[cfg(test)]
+pub use self::{
+    transport::{
+        PocketFakeTransport
+    }
+};
+
+////////////////////////////////////////////////////////////////////////
+
+/// Средняя скорость чтения для оценки времени, если Pocket ее не посчитал
+const WORDS_PER_MINUTE: i64 = 200;
+
+/// Сайт, на котором лежит материал
+#[derive(Deserialize, Debug, Clone)]
+pub struct PocketDomainMetadata{
+    pub name: Option<String>
+}
+
+/// Сохраненный элемент списка чтения
+/// https://getpocket.com/developer/docs/v3/add
+#[derive(Deserialize, Debug, Clone)]
+pub struct PocketItem{
+    #[serde(default, deserialize_with = "deserialize_optional_string")]
+    pub item_id: Option<String>,
+    pub given_url: Option<String>,
+    pub normal_url: Option<String>,
+    pub resolved_url: Option<String>,
+    pub title: Option<String>,
+    pub given_title: Option<String>,
+    pub resolved_title: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    pub word_count: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    pub time_to_read: Option<i64>,
+    pub domain_metadata: Option<PocketDomainMetadata>,
+    pub excerpt: Option<String>,
+    /// `0` - непрочитанный, `1` - в архиве, `2` - удален
+    #[serde(default, deserialize_with = "deserialize_optional_string")]
+    pub status: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_string")]
+    pub favorite: Option<String>,
+    /// Порядок элемента в выдаче get
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    pub sort_id: Option<i64>
+}
+
+impl PocketItem {
+    pub fn url(&self) -> Option<&str> {
+        self.resolved_url
+            .as_deref()
+            .or(self.normal_url.as_deref())
+            .or(self.given_url.as_deref())
+            .filter(|url| !url.is_empty())
+    }
+
+    /// Заголовок, пока страница не обработана Pocket он бывает пустым
+    pub fn title(&self) -> Option<&str> {
+        self.title
+            .iter()
+            .chain(self.resolved_title.iter())
+            .chain(self.given_title.iter())
+            .map(|title| title.trim())
+            .find(|title| !title.is_empty())
+    }
+
+    pub fn domain(&self) -> Option<String> {
+        let name = self.domain_metadata
+            .as_ref()
+            .and_then(|d| d.name.clone())
+            .filter(|name| !name.is_empty());
+        name.or_else(||{
+            self.url()
+                .and_then(|url| Url::parse(url).ok())
+                .and_then(|url| url.host_str().map(|host| host.trim_start_matches("www.").to_string()))
+        })
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.status.as_deref() == Some("1")
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.favorite.as_deref() == Some("1")
+    }
+
+    /// Время чтения в минутах
+    pub fn reading_time_minutes(&self) -> Option<i64> {
+        self.time_to_read
+            .filter(|minutes| *minutes > 0)
+            .or_else(||{
+                self.word_count
+                    .filter(|words| *words > 0)
+                    .map(|words| (words + WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE)
+            })
+    }
+
+    /// Краткая строка вида `domain · N min read`
+    pub fn summary(&self) -> Option<String> {
+        let mut details = Vec::new();
+        if let Some(domain) = self.domain() {
+            details.push(domain);
+        }
+        if let Some(minutes) = self.reading_time_minutes() {
+            details.push(format!("{} min read", minutes));
+        }
+        if details.is_empty() {
+            None
+        }else{
+            Some(details.join(" · "))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct PocketAddResponse{
+    item: PocketItem
+}
+
+/// Какие элементы выбирать по статусу прочтения
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PocketItemState{
+    Unread,
+    Archive,
+    All
+}
+
+/// Параметры выборки сохраненных элементов
+/// https://getpocket.com/developer/docs/v3/retrieve
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PocketGetQuery{
+    /// Поиск по заголовку и адресу
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<PocketItemState>,
+
+    /// `1` - только избранное, `0` - только не избранное
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favorite: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>
+}
+
+/// Действие для пакетного изменения списка
+/// https://getpocket.com/developer/docs/v3/modify
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PocketAction{
+    Add{
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tags: Option<String>
+    },
+    Archive{
+        item_id: String
+    },
+    Readd{
+        item_id: String
+    },
+    Favorite{
+        item_id: String
+    },
+    Unfavorite{
+        item_id: String
+    },
+    Delete{
+        item_id: String
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct PocketSendResponse{
+    #[serde(default)]
+    action_results: Vec<Value>
+}
+
+#[derive(Deserialize, Debug)]
+struct PocketGetResponse{
+    #[serde(default, deserialize_with = "deserialize_item_list")]
+    list: Vec<PocketItem>
+}
+
+/// Pocket отдает элементы словарем по item_id, а пустой результат - пустым массивом.
+/// Порядок восстанавливаем по `sort_id`.
+fn deserialize_item_list<'de, D>(deserializer: D) -> Result<Vec<PocketItem>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    let value = Value::deserialize(deserializer)?;
+    let mut items = match value {
+        Value::Object(map) => {
+            map.into_iter()
+                .map(|(_, item)| PocketItem::deserialize(item))
+                .collect::<Result<Vec<PocketItem>, _>>()
+                .map_err(serde::de::Error::custom)?
+        },
+        _ => Vec::new()
+    };
+    items.sort_by_key(|item| item.sort_id);
+    Ok(items)
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Работа с элементами списка чтения пользователя через Pocket v3 API.
+/// Авторизация остается на `pocket_api_client`, здесь только операции с сохраненными ссылками.
+#[derive(Debug, Clone)]
+pub struct PocketItemsClient{
+    transport: Arc<dyn PocketTransport>
+}
+
+impl PocketItemsClient {
+    pub fn new(http_client: Client, consumer_key: String) -> PocketItemsClient{
+        PocketItemsClient::with_transport(Arc::new(PocketHttpTransport::new(http_client, consumer_key)))
+    }
+
+    /// Клиент поверх любого транспорта, например фейкового в тестах
+    pub fn with_transport(transport: Arc<dyn PocketTransport>) -> PocketItemsClient{
+        PocketItemsClient{
+            transport
+        }
+    }
+
+    #[instrument(skip(self, access_token, params))]
+    async fn call_method<R>(&self, method: &str, access_token: &str, params: Value) -> Result<R, TelegramBotError>
+    where
+        R: DeserializeOwned + Send + 'static
+    {
+        let response = self
+            .transport
+            .call(method, access_token, params)
+            .await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Сохранение ссылки в список чтения
+    #[instrument(skip(self, access_token))]
+    pub async fn add(&self, access_token: &str, url: &str, tags: &[String]) -> Result<PocketItem, TelegramBotError> {
+        let mut params = json!({
+            "url": url
+        });
+        if !tags.is_empty() {
+            params["tags"] = json!(tags.join(","));
+        }
+
+        let response: PocketAddResponse = self
+            .call_method("add", access_token, params)
+            .await?;
+
+        Ok(response.item)
+    }
+
+    /// Пакетное выполнение действий одним запросом.
+    /// Для каждого действия возвращается признак успеха, порядок сохраняется.
+    #[instrument(skip(self, access_token, actions), fields(actions_count = actions.len()))]
+    pub async fn send(&self, access_token: &str, actions: &[PocketAction]) -> Result<Vec<bool>, TelegramBotError> {
+        let response: PocketSendResponse = self
+            .call_method("send", access_token, json!({
+                "actions": actions
+            }))
+            .await?;
+
+        // При успехе Pocket отдает `true` или сам элемент, при ошибке - `false`
+        let results = response
+            .action_results
+            .iter()
+            .map(|result| !matches!(result, Value::Bool(false) | Value::Null))
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Выборка сохраненных элементов, новые первыми
+    #[instrument(skip(self, access_token))]
+    pub async fn get(&self, access_token: &str, query: &PocketGetQuery) -> Result<Vec<PocketItem>, TelegramBotError> {
+        let mut params = serde_json::to_value(query)?;
+        params["sort"] = json!("newest");
+        params["detailType"] = json!("simple");
+
+        let response: PocketGetResponse = self
+            .call_method("get", access_token, params)
+            .await?;
+
+        Ok(response.list)
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    collections::{
+        HashMap,
+        VecDeque
+    },
+    sync::{
+        Mutex
+    }
+};
+use futures::{
+    future::{
+        self,
+        BoxFuture,
+        FutureExt
+    }
+};
+use reqwest::{
+    StatusCode
+};
+use serde_json::{
+    Value
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+use super::{
+    PocketTransport
+};
+
+/// Транспорт без сети: ответы заранее складываются в очередь по имени метода,
+/// а все вызовы запоминаются для проверки
+#[derive(Debug, Default)]
+pub struct PocketFakeTransport{
+    responses: Mutex<HashMap<String, VecDeque<Result<Value, StatusCode>>>>,
+    calls: Mutex<Vec<(String, Value)>>
+}
+
+impl PocketFakeTransport {
+    /// Успешный ответ с указанным телом
+    pub fn push_result(&self, method: &str, response: Value){
+        self.push(method, Ok(response));
+    }
+
+    /// Ошибка с указанным HTTP статусом
+    pub fn push_error(&self, method: &str, status: StatusCode){
+        self.push(method, Err(status));
+    }
+
+    fn push(&self, method: &str, response: Result<Value, StatusCode>){
+        self.responses
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .entry(method.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Все вызовы по порядку: имя метода и параметры без токена
+    pub fn calls(&self) -> Vec<(String, Value)>{
+        self.calls
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .clone()
+    }
+
+    fn next_response(&self, method: &str, params: Value) -> Result<Value, TelegramBotError>{
+        self.calls
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .push((method.to_string(), params));
+
+        let response = self.responses
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .get_mut(method)
+            .and_then(|queue| queue.pop_front())
+            .ok_or_else(|| TelegramBotError::TransportError(format!("No fake response for Pocket method {}", method)))?;
+        response.map_err(|status| TelegramBotError::PocketItemsError(status, None, None))
+    }
+}
+
+impl PocketTransport for PocketFakeTransport {
+    fn call<'a>(&'a self, method: &'a str, _access_token: &'a str, params: Value) -> BoxFuture<'a, Result<Value, TelegramBotError>>{
+        future::ready(self.next_response(method, params)).boxed()
+    }
+}
@@ -0,0 +1,92 @@
+use futures::{
+    future::{
+        BoxFuture,
+        FutureExt
+    }
+};
+use reqwest::{
+    Client
+};
+use url::{
+    Url
+};
+use serde_json::{
+    json,
+    Value
+};
+use tracing::{
+    debug,
+    trace
+};
+use reqwest_inspect_json::{
+    InspectJson
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+use super::{
+    PocketTransport
+};
+
+/// Настоящие запросы к Pocket через reqwest
+#[derive(Debug)]
+pub struct PocketHttpTransport{
+    http_client: Client,
+    consumer_key: String,
+    api_url: Url
+}
+
+impl PocketHttpTransport {
+    pub fn new(http_client: Client, consumer_key: String) -> PocketHttpTransport{
+        PocketHttpTransport{
+            http_client,
+            consumer_key,
+            api_url: Url::parse("https://getpocket.com/v3/").expect("Invalid pocket api url")
+        }
+    }
+
+    async fn execute_call(&self, method: &str, access_token: &str, mut params: Value) -> Result<Value, TelegramBotError>{
+        let url = self.api_url.join(method)?;
+        trace!("Pocket method url: {}", url);
+
+        params["consumer_key"] = json!(self.consumer_key);
+        params["access_token"] = json!(access_token);
+
+        let response = self
+            .http_client
+            .post(url)
+            .header("X-Accept", "application/json")
+            .json(&params)
+            .send()
+            .await?;
+
+        // Описание ошибки Pocket передает в заголовках
+        let status = response.status();
+        if !status.is_success() {
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_string())
+            };
+            let code = header("X-Error-Code").and_then(|code| code.parse().ok());
+            let message = header("X-Error");
+            return Err(TelegramBotError::PocketItemsError(status, code, message));
+        }
+
+        let result = response
+            .inspect_json::<Value, TelegramBotError>(|d| { debug!("Pocket response: {}", d) })
+            .await?;
+
+        Ok(result)
+    }
+}
+
+impl PocketTransport for PocketHttpTransport {
+    fn call<'a>(&'a self, method: &'a str, access_token: &'a str, params: Value) -> BoxFuture<'a, Result<Value, TelegramBotError>>{
+        self.execute_call(method, access_token, params).boxed()
+    }
+}
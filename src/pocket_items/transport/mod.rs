@@ -0,0 +1,41 @@
+mod http;
+#[cfg(test)]
+mod fake;
+
+use std::{
+    fmt::{
+        Debug
+    }
+};
+use futures::{
+    future::{
+        BoxFuture
+    }
+};
+use serde_json::{
+    Value
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+
+pub use self::{
+    http::{
+        PocketHttpTransport
+    }
+};
+#[cfg(test)]
+pub use self::{
+    fake::{
+        PocketFakeTransport
+    }
+};
+
+/// Способ доставки запросов до Pocket v3 API, так же как у клиента Telegram
+/// его можно подменить на фейковый в тестах
+pub trait PocketTransport: Debug + Send + Sync {
+    /// Вызов метода с токеном пользователя, возвращает разобранное тело успешного ответа
+    fn call<'a>(&'a self, method: &'a str, access_token: &'a str, params: Value) -> BoxFuture<'a, Result<Value, TelegramBotError>>;
+}
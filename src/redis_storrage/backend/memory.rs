@@ -0,0 +1,69 @@
+use std::{
+    collections::{
+        HashMap
+    },
+    sync::{
+        Mutex
+    },
+    time::{
+        Duration
+    }
+};
+use futures::{
+    future::{
+        self,
+        BoxFuture,
+        FutureExt
+    }
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+use super::{
+    StorageBackend
+};
+
+/// Данные в памяти для тестов, время жизни ключей не учитывается
+#[derive(Debug, Default)]
+pub struct MemoryStorageBackend{
+    values: Mutex<HashMap<String, String>>
+}
+
+impl MemoryStorageBackend {
+    fn values(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>>{
+        self.values
+            .lock()
+            .expect("Memory storage lock poisoned")
+    }
+}
+
+impl StorageBackend for MemoryStorageBackend {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>, TelegramBotError>>{
+        let value = self.values().get(key).cloned();
+        future::ready(Ok(value)).boxed()
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: String, _ttl: Option<Duration>) -> BoxFuture<'a, Result<(), TelegramBotError>>{
+        self.values().insert(key.to_string(), value);
+        future::ready(Ok(())).boxed()
+    }
+
+    fn del<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), TelegramBotError>>{
+        self.values().remove(key);
+        future::ready(Ok(())).boxed()
+    }
+
+    fn rename<'a>(&'a self, old_key: &'a str, new_key: &'a str) -> BoxFuture<'a, Result<bool, TelegramBotError>>{
+        let mut values = self.values();
+        let moved = match values.remove(old_key) {
+            Some(value) => {
+                values.insert(new_key.to_string(), value);
+                true
+            },
+            None => false
+        };
+        future::ready(Ok(moved)).boxed()
+    }
+}
@@ -0,0 +1,48 @@
+mod redis;
+#[cfg(test)]
+mod memory;
+
+use std::{
+    fmt::{
+        Debug
+    },
+    time::{
+        Duration
+    }
+};
+use futures::{
+    future::{
+        BoxFuture
+    }
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+
+pub use self::{
+    redis::{
+        RedisStorageBackend
+    }
+};
+#[cfg(test)]
+pub use self::{
+    memory::{
+        MemoryStorageBackend
+    }
+};
+
+/// Где физически лежат данные. Хранилище работает только со строками по ключу,
+/// поэтому Redis можно подменить на память в тестах.
+pub trait StorageBackend: Debug + Send + Sync {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>, TelegramBotError>>;
+
+    /// Без `ttl` ключ хранится бессрочно
+    fn set<'a>(&'a self, key: &'a str, value: String, ttl: Option<Duration>) -> BoxFuture<'a, Result<(), TelegramBotError>>;
+
+    fn del<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), TelegramBotError>>;
+
+    /// Перенос значения на другой ключ вместе со временем жизни, `false` если исходного ключа нет
+    fn rename<'a>(&'a self, old_key: &'a str, new_key: &'a str) -> BoxFuture<'a, Result<bool, TelegramBotError>>;
+}
@@ -0,0 +1,124 @@
+use std::{
+    time::{
+        Duration
+    }
+};
+use futures::{
+    future::{
+        BoxFuture,
+        FutureExt
+    }
+};
+use redis::{
+    AsyncCommands
+};
+use bb8::{
+    Pool
+};
+use bb8_redis::{
+    RedisConnectionManager
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+use super::{
+    StorageBackend
+};
+
+/// Данные в Redis через пул соединений
+#[derive(Debug)]
+pub struct RedisStorageBackend{
+    redis_pool: Pool<RedisConnectionManager>
+}
+
+impl RedisStorageBackend {
+    pub fn new(redis_pool: Pool<RedisConnectionManager>) -> RedisStorageBackend{
+        RedisStorageBackend{
+            redis_pool
+        }
+    }
+
+    async fn execute_get(&self, key: &str) -> Result<Option<String>, TelegramBotError>{
+        let mut conn = self
+            .redis_pool
+            .get()
+            .await?;
+
+        let value: Option<String> = conn
+            .get(key)
+            .await?;
+
+        Ok(value)
+    }
+
+    async fn execute_set(&self, key: &str, value: String, ttl: Option<Duration>) -> Result<(), TelegramBotError>{
+        let mut conn = self
+            .redis_pool
+            .get()
+            .await?;
+
+        if let Some(ttl) = ttl {
+            let seconds = ttl.as_secs() as usize;
+            let _: () = conn
+                .set_ex(key, value, seconds)
+                .await?;
+        }else{
+            let _: () = conn
+                .set(key, value)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn execute_del(&self, key: &str) -> Result<(), TelegramBotError>{
+        let mut conn = self
+            .redis_pool
+            .get()
+            .await?;
+
+        let _: () = conn
+            .del(key)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn execute_rename(&self, old_key: &str, new_key: &str) -> Result<bool, TelegramBotError>{
+        let mut conn = self
+            .redis_pool
+            .get()
+            .await?;
+
+        let exists: bool = conn
+            .exists(old_key)
+            .await?;
+        if exists {
+            let _: () = conn
+                .rename(old_key, new_key)
+                .await?;
+        }
+
+        Ok(exists)
+    }
+}
+
+impl StorageBackend for RedisStorageBackend {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>, TelegramBotError>>{
+        self.execute_get(key).boxed()
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: String, ttl: Option<Duration>) -> BoxFuture<'a, Result<(), TelegramBotError>>{
+        self.execute_set(key, value, ttl).boxed()
+    }
+
+    fn del<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), TelegramBotError>>{
+        self.execute_del(key).boxed()
+    }
+
+    fn rename<'a>(&'a self, old_key: &'a str, new_key: &'a str) -> BoxFuture<'a, Result<bool, TelegramBotError>>{
+        self.execute_rename(old_key, new_key).boxed()
+    }
+}
@@ -1,19 +1,24 @@
+mod backend;
+
 use std::{
+    sync::{
+        Arc
+    },
     time::{
         Duration
     }
 };
-use redis::{
-    AsyncCommands
-};
 use bb8::{
     Pool
 };
 use bb8_redis::{
     RedisConnectionManager
 };
-use derive_more::{
-    Constructor
+use serde::{
+    de::{
+        DeserializeOwned
+    },
+    Serialize
 };
 use serde_json::{
     from_str,
@@ -25,37 +30,86 @@ use tracing::{
 };
 use crate::{
     telegram_client::{
-        TelegramUserId
+        TelegramChatId,
+        TelegramMessageId
     },
     model::{
-        UserState
+        UserState,
+        UserStats,
+        SearchSession,
+        SavedItem
     },
     error::{
         TelegramBotError
     }
 };
 
+pub use self::{
+    backend::{
+        StorageBackend,
+        RedisStorageBackend
+    }
+};
+#[cfg(test)]
+pub use self::{
+    backend::{
+        MemoryStorageBackend
+    }
+};
+
 
-#[derive(Debug, Constructor)]
+/// Ключи, которые относятся к конкретному чату, `{}` заменяется на идентификатор
+const CHAT_KEYS: &[&str] = &[
+    "user_state:{}:json",
+    "user_dashboard:{}",
+    "user_stats:{}:json",
+    "user_last_saved:{}:json"
+];
+
+#[derive(Debug)]
 pub struct RedisStorrage{
-    pub redis_pool: Pool<RedisConnectionManager>
+    backend: Arc<dyn StorageBackend>
 }
 
 impl RedisStorrage {
-    #[instrument(skip(self))]
-    pub async fn get_user_state(&self, user_id: TelegramUserId) -> Result<UserState, TelegramBotError> {
-        let state_str: Option<String> = {
-            let key = format!("user_state:{}:json", user_id);
-        
-            let mut conn = self
-                .redis_pool
-                .get()
-                .await?;
+    pub fn new(redis_pool: Pool<RedisConnectionManager>) -> RedisStorrage {
+        RedisStorrage::with_backend(Arc::new(RedisStorageBackend::new(redis_pool)))
+    }
 
-            conn
-                .get(key)
-                .await?
-        };
+    /// Хранилище поверх любого бэкенда, например в памяти для тестов
+    pub fn with_backend(backend: Arc<dyn StorageBackend>) -> RedisStorrage {
+        RedisStorrage{
+            backend
+        }
+    }
+
+    /// Значения хранятся в JSON, числа в нем совпадают с тем, как их хранит Redis
+    async fn get_json<T>(&self, key: &str) -> Result<Option<T>, TelegramBotError>
+    where
+        T: DeserializeOwned
+    {
+        match self.backend.get(key).await? {
+            Some(value) => Ok(Some(from_str(&value)?)),
+            None => Ok(None)
+        }
+    }
+
+    async fn set_json<T>(&self, key: &str, value: &T, ttl: Option<Duration>) -> Result<(), TelegramBotError>
+    where
+        T: Serialize + ?Sized
+    {
+        let value = to_string(value)?;
+        self.backend
+            .set(key, value, ttl)
+            .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_user_state(&self, chat_id: TelegramChatId) -> Result<UserState, TelegramBotError> {
+        let key = format!("user_state:{}:json", chat_id);
+        let state_str = self.backend
+            .get(&key)
+            .await?;
 
         if let Some(state_str) = state_str {
             debug!("User state exists: {}", state_str);
@@ -68,32 +122,132 @@ impl RedisStorrage {
     }
 
     #[instrument(skip(self))]
-    pub async fn set_user_state(&self, user_id: TelegramUserId, 
+    pub async fn set_user_state(&self, chat_id: TelegramChatId, 
                                        state: UserState, 
                                        ttl: Option<Duration>) -> Result<(), TelegramBotError> {
         let state_str = to_string(&state)?;
 
         debug!("User state set: {}", state_str);
 
-        let key = format!("user_state:{}:json", user_id);
+        let key = format!("user_state:{}:json", chat_id);
+        self.backend
+            .set(&key, state_str, ttl)
+            .await
+    }
 
-        let mut conn = self
-            .redis_pool
-            .get()
-            .await?;
+    /// Перенос состояния и связанных данных на новый идентификатор чата, когда группа
+    /// становится супергруппой. Время жизни ключей сохраняется.
+    #[instrument(skip(self))]
+    pub async fn move_user_state(&self, old_chat_id: TelegramChatId, new_chat_id: TelegramChatId) -> Result<(), TelegramBotError> {
+        for key_format in CHAT_KEYS.iter() {
+            let old_key = key_format.replace("{}", &old_chat_id.to_string());
+            let new_key = key_format.replace("{}", &new_chat_id.to_string());
 
-        if let Some(ttl) = ttl {
-            let seconds = ttl.as_secs() as usize;
-            conn
-                .set_ex(&key, &state_str, seconds)
-                .await?;
-        }else{
-            conn
-                .set(&key, &state_str)
+            let moved = self.backend
+                .rename(&old_key, &new_key)
                 .await?;
+            if moved {
+                debug!("Chat key moved: {} -> {}", old_key, new_key);
+            }
         }
 
         Ok(())
     }
-    
+
+    /// Закрепленное сообщение со статусом, если пользователь его создавал
+    #[instrument(skip(self))]
+    pub async fn get_dashboard_message_id(&self, chat_id: TelegramChatId) -> Result<Option<TelegramMessageId>, TelegramBotError> {
+        let key = format!("user_dashboard:{}", chat_id);
+        self.get_json(&key).await
+    }
+
+    /// `None` удаляет сохраненное сообщение
+    #[instrument(skip(self))]
+    pub async fn set_dashboard_message_id(&self, chat_id: TelegramChatId, message_id: Option<TelegramMessageId>) -> Result<(), TelegramBotError> {
+        let key = format!("user_dashboard:{}", chat_id);
+        match message_id {
+            Some(message_id) => self.backend.set(&key, message_id.to_string(), None).await,
+            None => self.backend.del(&key).await
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_user_stats(&self, chat_id: TelegramChatId) -> Result<UserStats, TelegramBotError> {
+        let key = format!("user_stats:{}:json", chat_id);
+        let stats = self
+            .get_json(&key)
+            .await?;
+        Ok(stats.unwrap_or_default())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn set_user_stats(&self, chat_id: TelegramChatId, stats: &UserStats) -> Result<(), TelegramBotError> {
+        let key = format!("user_stats:{}:json", chat_id);
+        self.set_json(&key, stats, None).await
+    }
+
+    /// Запрос поиска для сообщения с результатами
+    #[instrument(skip(self))]
+    pub async fn get_search_session(&self, chat_id: TelegramChatId, message_id: TelegramMessageId) -> Result<Option<SearchSession>, TelegramBotError> {
+        let key = format!("user_search:{}:{}:json", chat_id, message_id);
+        self.get_json(&key).await
+    }
+
+    /// Результаты поиска не нужны долго, поэтому сессия живет сутки
+    #[instrument(skip(self))]
+    pub async fn set_search_session(&self, chat_id: TelegramChatId, message_id: TelegramMessageId, session: &SearchSession) -> Result<(), TelegramBotError> {
+        let key = format!("user_search:{}:{}:json", chat_id, message_id);
+        self.set_json(&key, session, Some(Duration::from_secs(60 * 60 * 24))).await
+    }
+
+    /// Сохраненный элемент, к подтверждению которого прикреплены кнопки
+    #[instrument(skip(self))]
+    pub async fn get_saved_item(&self, chat_id: TelegramChatId, message_id: TelegramMessageId) -> Result<Option<SavedItem>, TelegramBotError> {
+        let key = format!("user_saved_item:{}:{}:json", chat_id, message_id);
+        self.get_json(&key).await
+    }
+
+    /// Кнопки под старыми подтверждениями не нужны вечно, элемент помним неделю
+    #[instrument(skip(self))]
+    pub async fn set_saved_item(&self, chat_id: TelegramChatId, message_id: TelegramMessageId, item: Option<&SavedItem>) -> Result<(), TelegramBotError> {
+        let key = format!("user_saved_item:{}:{}:json", chat_id, message_id);
+        match item {
+            Some(item) => self.set_json(&key, item, Some(Duration::from_secs(60 * 60 * 24 * 7))).await,
+            None => self.backend.del(&key).await
+        }
+    }
+
+    /// Подтверждения последнего сохранения, к ним применяются команды без ответа на сообщение
+    #[instrument(skip(self))]
+    pub async fn get_last_saved_messages(&self, chat_id: TelegramChatId) -> Result<Vec<TelegramMessageId>, TelegramBotError> {
+        let key = format!("user_last_saved:{}:json", chat_id);
+        let message_ids = self
+            .get_json(&key)
+            .await?;
+        Ok(message_ids.unwrap_or_default())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn set_last_saved_messages(&self, chat_id: TelegramChatId, message_ids: &[TelegramMessageId]) -> Result<(), TelegramBotError> {
+        let key = format!("user_last_saved:{}:json", chat_id);
+        self.set_json(&key, message_ids, None).await
+    }
+
+    /// Следующий offset для getUpdates, сохраненный после обработки последнего обновления
+    #[instrument(skip(self))]
+    pub async fn get_updates_offset(&self) -> Result<i64, TelegramBotError> {
+        let offset: Option<i64> = self
+            .get_json("telegram_updates:offset")
+            .await?;
+        debug!("Saved updates offset: {:?}", offset);
+
+        Ok(offset.unwrap_or(0))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn set_updates_offset(&self, offset: i64) -> Result<(), TelegramBotError> {
+        self.backend
+            .set("telegram_updates:offset", offset.to_string(), None)
+            .await
+    }
 }
\ No newline at end of file
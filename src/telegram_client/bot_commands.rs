@@ -0,0 +1,23 @@
+use serde::{
+    Serialize
+};
+
+/// Команда в меню бота
+/// https://core.telegram.org/bots/api#botcommand
+#[derive(Serialize, Debug, Clone)]
+pub struct TelegramBotCommand{
+    /// Имя без `/`, только строчные буквы, цифры и `_`
+    pub command: String,
+    pub description: String
+}
+
+/// Для кого показывается список команд
+/// https://core.telegram.org/bots/api#botcommandscope
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)] // Имена повторяют типы областей из Bot API
+pub enum TelegramBotCommandScope{
+    AllPrivateChats,
+    AllGroupChats,
+    AllChatAdministrators
+}
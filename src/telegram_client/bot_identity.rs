@@ -0,0 +1,44 @@
+use url::{
+    Url
+};
+use serde::{
+    Deserialize
+};
+
+/// Информация о самом боте
+/// https://core.telegram.org/bots/api#getme
+#[derive(Deserialize, Debug, Clone)]
+pub struct TelegramBotUserData{
+    pub username: String,
+    /// Включено ли добавление в группы в BotFather
+    pub can_join_groups: Option<bool>,
+    /// Включен ли inline режим в BotFather
+    pub supports_inline_queries: Option<bool>
+}
+
+/// Кто мы такие, получается из getMe при старте
+#[derive(Debug, Clone)]
+pub struct TelegramBotIdentity{
+    pub username: String,
+    /// Ссылка на чат с ботом вида `https://t.me/username`
+    pub url: Url
+}
+
+impl TelegramBotIdentity {
+    pub fn new(data: TelegramBotUserData) -> TelegramBotIdentity{
+        let url = Url::parse("https://t.me/")
+            .and_then(|base| base.join(&data.username))
+            .expect("Invalid bot url");
+        TelegramBotIdentity{
+            username: data.username,
+            url
+        }
+    }
+
+    /// Обращение вида `@botname` относится к нам, регистр в Telegram не важен
+    pub fn is_mentioned(&self, mention: &str) -> bool{
+        mention
+            .trim_start_matches('@')
+            .eq_ignore_ascii_case(&self.username)
+    }
+}
@@ -0,0 +1,19 @@
+use std::{
+    time::{
+        Duration
+    }
+};
+use serde::{
+    Serialize
+};
+
+/// Действие действует 5 секунд или до следующего сообщения, обновляем чуть раньше
+pub const CHAT_ACTION_REFRESH_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Статус бота, который видит пользователь в шапке чата
+/// https://core.telegram.org/bots/api#sendchataction
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TelegramChatAction{
+    Typing
+}
@@ -1,46 +1,73 @@
 use std::{
     sync::{
         Arc
+    },
+    future::{
+        Future
     }
 };
-use reqwest::{
-    Client
-};
 use url::{
     Url
 };
 use tracing::{
     instrument,
     debug,
-    trace
+    warn
 };
 use serde_json::{
-    json
-};
-use reqwest_inspect_json::{
-    InspectJson
+    json,
+    Value
 };
 use crate::{
     error::{
         TelegramBotError
-    },
-    helpers::{
-        DataOrErrorResponse
     }
 };
 use super::{
     responses::{
-        TelegramErrorResponse,
-        TelegramUpdatesResponse,
+        TelegramChatId,
+        TelegramMessageData,
+        TelegramMessageId,
+        TelegramLinkPreviewOptions,
+        TelegramInlineQueryResultArticle,
+        TelegramInlineQueryResultsButton,
+        TelegramFileData,
+        TelegramReactionTypeData,
         TelegramUserId,
-        TelegramMessageResponse,
-        TelegramMessageId
+        TelegramChatMemberData
+    },
+    updates::{
+        TelegramUpdateData
+    },
+    reply_markup::{
+        TelegramReplyMarkup,
+        TelegramInlineKeyboardMarkup
+    },
+    bot_identity::{
+        TelegramBotUserData
+    },
+    bot_commands::{
+        TelegramBotCommand,
+        TelegramBotCommandScope
+    },
+    formatted_text::{
+        TelegramOutgoingText,
+        TelegramFormattedText,
+        TELEGRAM_MESSAGE_MAX_LENGTH
+    },
+    chat_action::{
+        TelegramChatAction,
+        CHAT_ACTION_REFRESH_INTERVAL
     },
     config::{
         TelegramClientConfig
     },
+    transport::{
+        TelegramTransport
+    },
     message::{
-        TelegramMessage
+        TelegramMessage,
+        TelegramMessageHandle
     }
 };
 
@@ -51,89 +78,467 @@ pub struct TelegramClient {
 }
 
 impl TelegramClient {
-    pub fn new(http_client: Client, api_url: Url) -> TelegramClient {
+    /// Клиент с произвольным транспортом, например фейковым или с записью обмена
+    pub fn with_transport(transport: Arc<dyn TelegramTransport>) -> TelegramClient {
+        TelegramClient{
+            config: Arc::new(TelegramClientConfig::new(transport))
+        }
+    }
+
+    /// Действия с уже отправленным сообщением по его чату и идентификатору
+    pub fn message_handle(&self, chat_id: TelegramChatId, message_id: TelegramMessageId) -> TelegramMessageHandle {
+        TelegramMessageHandle::new(self.config.clone(), chat_id, message_id)
+    }
+
+    /// Клиент поверх уже существующей конфигурации, нужен для методов у `TelegramMessageHandle`
+    pub(super) fn from_config(config: Arc<TelegramClientConfig>) -> TelegramClient {
         TelegramClient{
-            config: Arc::new(TelegramClientConfig::new(http_client, api_url))
+            config
         }
     }
 
+    /// Информация о боте, заодно проверяет валидность токена
+    /// https://core.telegram.org/bots/api#getme
     #[instrument(skip(self))]
-    pub async fn get_updates(&self, last_update_id: i64) -> Result<TelegramUpdatesResponse, TelegramBotError> {
-        let get_updates_url = self
+    pub async fn get_me(&self) -> Result<TelegramBotUserData, TelegramBotError> {
+        let me = self
             .config
-            .api_url
-            .join("getUpdates")
-            .expect("Get updates url create failed");
-        trace!("Get updates url: {}", get_updates_url);
+            .call_method("getMe", json!({}))
+            .await?;
 
+        Ok(me)
+    }
+
+    /// Long polling обновлений, `allowed_updates` ограничивает типы присылаемых обновлений
+    #[instrument(skip(self))]
+    pub async fn get_updates(&self, last_update_id: i64, allowed_updates: &[&str]) -> Result<Vec<TelegramUpdateData>, TelegramBotError> {
         let updates = self
             .config
-            .http_client
-            .get(get_updates_url)
-            .json(&json!({
+            .call_method("getUpdates", json!({
                 "timeout": 60,
-                "offset": last_update_id
+                "offset": last_update_id,
+                "allowed_updates": allowed_updates
             }))
-            .send()
-            .await?
-            .inspect_json::<DataOrErrorResponse<TelegramUpdatesResponse, TelegramErrorResponse>, 
-                            TelegramBotError>(|d|{ debug!("Update json: {}", d); })
-            .await?
-            .into_result()?;
+            .await?;
 
         Ok(updates)
     }
 
     #[instrument(skip(self))]
-    pub async fn send_message(&self, user_id: TelegramUserId, msg: String) -> Result<TelegramMessage, TelegramBotError> {
-        let url = self.config.api_url.join("sendMessage")?;
-        trace!("Message url: {}", url);
+    pub async fn send_message(&self, chat_id: TelegramChatId, msg: String) -> Result<TelegramMessage, TelegramBotError> {
+        self.send_message_with_markup(chat_id, msg, None).await
+    }
+
+    /// Отправка сообщения с клавиатурой
+    #[instrument(skip(self))]
+    pub async fn send_message_with_markup(&self, 
+                                          chat_id: TelegramChatId, 
+                                          msg: String, 
+                                          reply_markup: Option<TelegramReplyMarkup>) -> Result<TelegramMessage, TelegramBotError> {
+        let mut text = TelegramOutgoingText::from(msg);
+        text.reply_markup = reply_markup;
+        self.send_text(chat_id, text).await
+    }
+
+    /// Отправка сообщения с разметкой, настройками превью и клавиатурой
+    #[instrument(skip(self))]
+    pub async fn send_text(&self, chat_id: TelegramChatId, text: TelegramOutgoingText) -> Result<TelegramMessage, TelegramBotError> {
+        let mut params = serde_json::to_value(text)?;
+        params["chat_id"] = json!(chat_id);
 
-        let message_resp = self
+        let message_data: TelegramMessageData = self
             .config
-            .http_client
-            .post(url)
-            .json(&json!({
-                "chat_id": user_id,
-                "text": msg
-            }))
-            .send()
-            .await?
-            .inspect_json::<DataOrErrorResponse<TelegramMessageResponse, TelegramErrorResponse>, 
-                            TelegramBotError>(|d| { debug!("User message response: {}", d) })
-            .await?
-            .into_result()?;
-        debug!("Received message: {:#?}", message_resp);
+            .call_method("sendMessage", params)
+            .await?;
+        debug!("Received message: {:#?}", message_data);
 
-        Ok(TelegramMessage::new(self.config.clone(), message_resp.result))
+        Ok(TelegramMessage::new(self.config.clone(), message_data))
     }
 
+    /// Ответ на конкретное сообщение в том же чате
     #[instrument(skip(self))]
-    pub async fn update_message_text_by_id(&self, 
-                                           user_id: TelegramUserId, 
-                                           message_id: TelegramMessageId, 
-                                           new_text: String) -> Result<TelegramMessage, TelegramBotError>{
-        let url = self.config.api_url.join("editMessageText")?;
-        trace!("Message url: {}", url);
+    pub async fn send_reply(&self, 
+                            chat_id: TelegramChatId, 
+                            reply_to_message_id: TelegramMessageId, 
+                            text: TelegramOutgoingText) -> Result<TelegramMessage, TelegramBotError> {
+        let mut params = serde_json::to_value(text)?;
+        params["chat_id"] = json!(chat_id);
+        params["reply_parameters"] = json!({
+            "message_id": reply_to_message_id,
+            "allow_sending_without_reply": true
+        });
+
+        let message_data: TelegramMessageData = self
+            .config
+            .call_method("sendMessage", params)
+            .await?;
+        debug!("Received message: {:#?}", message_data);
 
+        Ok(TelegramMessage::new(self.config.clone(), message_data))
+    }
+
+    /// Отправка длинного текста несколькими сообщениями по порядку.
+    /// Настройки превью применяются ко всем частям, клавиатура прикрепляется к последней.
+    #[instrument(skip(self, text))]
+    pub async fn send_text_chunked(&self, 
+                                   chat_id: TelegramChatId, 
+                                   text: TelegramFormattedText,
+                                   link_preview_options: Option<TelegramLinkPreviewOptions>,
+                                   reply_markup: Option<TelegramReplyMarkup>) -> Result<Vec<TelegramMessage>, TelegramBotError> {
+        let chunks = text.split(TELEGRAM_MESSAGE_MAX_LENGTH);
+        let chunks_count = chunks.len();
+        debug!("Message split into {} chunks", chunks_count);
+
+        let mut messages = Vec::with_capacity(chunks_count);
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            let mut text = TelegramOutgoingText::from(chunk);
+            text.link_preview_options = link_preview_options.clone();
+            if index + 1 == chunks_count {
+                text.reply_markup = reply_markup.clone();
+            }
+            messages.push(self.send_text(chat_id, text).await?);
+        }
+
+        Ok(messages)
+    }
+
+    /// Изменение текста сообщения с разметкой.
+    /// Изменять можно только inline клавиатуру, другие варианты Telegram отклонит.
+    #[instrument(skip(self))]
+    pub async fn edit_text_by_id(&self, 
+                                 chat_id: TelegramChatId, 
+                                 message_id: TelegramMessageId, 
+                                 text: TelegramOutgoingText) -> Result<TelegramMessage, TelegramBotError>{
         // https://core.telegram.org/bots/api#updating-messages
-        let message_resp = self
+        let mut params = serde_json::to_value(text)?;
+        params["chat_id"] = json!(chat_id);
+        params["message_id"] = json!(message_id);
+
+        let message_data: TelegramMessageData = self
+            .config
+            .call_method("editMessageText", params)
+            .await?;
+        debug!("Received message: {:#?}", message_data);
+
+        Ok(TelegramMessage::new(self.config.clone(), message_data))
+    }
+
+    /// Ответ на нажатие inline кнопки, без него у пользователя будет висеть индикатор загрузки
+    /// https://core.telegram.org/bots/api#answercallbackquery
+    #[instrument(skip(self))]
+    pub async fn answer_callback_query(&self, 
+                                       callback_query_id: String, 
+                                       text: Option<String>, 
+                                       show_alert: bool) -> Result<(), TelegramBotError>{
+        let mut params = json!({
+            "callback_query_id": callback_query_id,
+            "show_alert": show_alert
+        });
+        if let Some(text) = text {
+            params["text"] = Value::String(text);
+        }
+
+        let _: bool = self
+            .config
+            .call_method("answerCallbackQuery", params)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Ответ на inline запрос. `is_personal` не дает Telegram отдавать закешированные
+    /// результаты другим пользователям, пустой `next_offset` означает конец списка.
+    /// https://core.telegram.org/bots/api#answerinlinequery
+    #[instrument(skip(self, results))]
+    pub async fn answer_inline_query(&self, 
+                                     inline_query_id: String, 
+                                     results: &[TelegramInlineQueryResultArticle], 
+                                     cache_time: u32, 
+                                     is_personal: bool, 
+                                     next_offset: Option<String>, 
+                                     button: Option<TelegramInlineQueryResultsButton>) -> Result<(), TelegramBotError>{
+        let mut params = json!({
+            "inline_query_id": inline_query_id,
+            "results": results,
+            "cache_time": cache_time,
+            "is_personal": is_personal
+        });
+        if let Some(next_offset) = next_offset {
+            params["next_offset"] = Value::String(next_offset);
+        }
+        if let Some(button) = button {
+            params["button"] = serde_json::to_value(button)?;
+        }
+
+        let _: bool = self
+            .config
+            .call_method("answerInlineQuery", params)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Замена клавиатуры у уже отправленного сообщения, `None` убирает клавиатуру
+    /// https://core.telegram.org/bots/api#editmessagereplymarkup
+    #[instrument(skip(self))]
+    pub async fn edit_message_reply_markup(&self, 
+                                           chat_id: TelegramChatId, 
+                                           message_id: TelegramMessageId, 
+                                           reply_markup: Option<TelegramInlineKeyboardMarkup>) -> Result<TelegramMessage, TelegramBotError>{
+        let mut params = json!({
+            "chat_id": chat_id,
+            "message_id": message_id
+        });
+        if let Some(reply_markup) = reply_markup {
+            params["reply_markup"] = serde_json::to_value(reply_markup)?;
+        }
+
+        let message_data: TelegramMessageData = self
+            .config
+            .call_method("editMessageReplyMarkup", params)
+            .await?;
+        debug!("Received message: {:#?}", message_data);
+
+        Ok(TelegramMessage::new(self.config.clone(), message_data))
+    }
+
+    /// Информация о файле для скачивания
+    /// https://core.telegram.org/bots/api#getfile
+    #[instrument(skip(self))]
+    pub async fn get_file(&self, file_id: &str) -> Result<TelegramFileData, TelegramBotError>{
+        let file = self
+            .config
+            .call_method("getFile", json!({
+                "file_id": file_id
+            }))
+            .await?;
+
+        Ok(file)
+    }
+
+    /// Скачивание содержимого файла, не больше `max_size` байт.
+    /// Размер проверяется заранее, если он известен, и транспортом по мере получения данных.
+    #[instrument(skip(self))]
+    pub async fn download_file(&self, file: &TelegramFileData, max_size: u64) -> Result<Vec<u8>, TelegramBotError>{
+        if file.file_size.filter(|size| *size > max_size).is_some() {
+            return Err(TelegramBotError::FileTooLarge(max_size));
+        }
+
+        let file_path = file
+            .file_path
+            .as_ref()
+            .ok_or(TelegramBotError::FileIsNotAvailable)?;
+
+        self.config
+            .transport
+            .download(file_path, max_size)
+            .await
+    }
+
+    /// https://core.telegram.org/bots/api#sendchataction
+    #[instrument(skip(self))]
+    pub async fn send_chat_action(&self, chat_id: TelegramChatId, action: TelegramChatAction) -> Result<(), TelegramBotError>{
+        let _: bool = self
+            .config
+            .call_method("sendChatAction", json!({
+                "chat_id": chat_id,
+                "action": action
+            }))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Показываем действие в чате, пока выполняется `future`.
+    /// Ошибка отправки действия не влияет на результат, просто перестаем его обновлять.
+    pub async fn with_chat_action<F>(&self, 
+                                     chat_id: TelegramChatId, 
+                                     action: TelegramChatAction, 
+                                     future: F) -> F::Output
+    where
+        F: Future
+    {
+        let refresh = async {
+            let mut interval = tokio::time::interval(CHAT_ACTION_REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(err) = self.send_chat_action(chat_id, action).await {
+                    warn!("Chat action send failed: {}", err);
+                    break;
+                }
+            }
+        };
+        tokio::pin!(future);
+        tokio::pin!(refresh);
+
+        tokio::select! {
+            result = &mut future => result,
+            _ = refresh => future.await
+        }
+    }
+
+    /// Регистрация адреса для получения обновлений через webhook.
+    /// Секрет будет приходить в заголовке `X-Telegram-Bot-Api-Secret-Token`.
+    /// https://core.telegram.org/bots/api#setwebhook
+    #[instrument(skip(self, secret_token))]
+    pub async fn set_webhook(&self, url: Url, secret_token: String, allowed_updates: &[&str]) -> Result<(), TelegramBotError>{
+        let _: bool = self
+            .config
+            .call_method("setWebhook", json!({
+                "url": url.as_str(),
+                "secret_token": secret_token,
+                "allowed_updates": allowed_updates
+            }))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Отключение webhook, без этого getUpdates не работает
+    /// https://core.telegram.org/bots/api#deletewebhook
+    #[instrument(skip(self))]
+    pub async fn delete_webhook(&self) -> Result<(), TelegramBotError>{
+        let _: bool = self
             .config
-            .http_client
-            .post(url)
-            .json(&json!({
-                "chat_id": user_id,
+            .call_method("deleteWebhook", json!({}))
+            .await?;
+
+        Ok(())
+    }
+
+    /// https://core.telegram.org/bots/api#deletemessage
+    #[instrument(skip(self))]
+    pub async fn delete_message(&self, chat_id: TelegramChatId, message_id: TelegramMessageId) -> Result<(), TelegramBotError>{
+        let _: bool = self
+            .config
+            .call_method("deleteMessage", json!({
+                "chat_id": chat_id,
+                "message_id": message_id
+            }))
+            .await?;
+
+        Ok(())
+    }
+
+    /// https://core.telegram.org/bots/api#pinchatmessage
+    #[instrument(skip(self))]
+    pub async fn pin_message(&self, 
+                             chat_id: TelegramChatId, 
+                             message_id: TelegramMessageId, 
+                             disable_notification: bool) -> Result<(), TelegramBotError>{
+        let _: bool = self
+            .config
+            .call_method("pinChatMessage", json!({
+                "chat_id": chat_id,
+                "message_id": message_id,
+                "disable_notification": disable_notification
+            }))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Статус участника в чате, например для проверки прав администратора
+    /// https://core.telegram.org/bots/api#getchatmember
+    #[instrument(skip(self))]
+    pub async fn get_chat_member(&self, chat_id: TelegramChatId, user_id: TelegramUserId) -> Result<TelegramChatMemberData, TelegramBotError>{
+        let member = self
+            .config
+            .call_method("getChatMember", json!({
+                "chat_id": chat_id,
+                "user_id": user_id
+            }))
+            .await?;
+
+        Ok(member)
+    }
+
+    /// Реакция бота на сообщение, `None` убирает реакцию
+    /// https://core.telegram.org/bots/api#setmessagereaction
+    #[instrument(skip(self))]
+    pub async fn set_message_reaction(&self, 
+                                      chat_id: TelegramChatId, 
+                                      message_id: TelegramMessageId, 
+                                      reaction: Option<TelegramReactionTypeData>) -> Result<(), TelegramBotError>{
+        let reaction: Vec<TelegramReactionTypeData> = reaction
+            .into_iter()
+            .collect();
+
+        let _: bool = self
+            .config
+            .call_method("setMessageReaction", json!({
+                "chat_id": chat_id,
                 "message_id": message_id,
-                "text": new_text
+                "reaction": reaction
             }))
-            .send()
-            .await?
-            .inspect_json::<DataOrErrorResponse<TelegramMessageResponse, TelegramErrorResponse>, 
-                            TelegramBotError>(|d| { debug!("User message response: {}", d) })
-            .await?
-            .into_result()?;
-        debug!("Received message: {:#?}", message_resp);
+            .await?;
 
-        Ok(TelegramMessage::new(self.config.clone(), message_resp.result))
+        Ok(())
     }
-}
\ No newline at end of file
+
+    /// Список команд в меню бота для указанной области и языка.
+    /// Без языка список показывается всем, для кого нет отдельного перевода.
+    /// https://core.telegram.org/bots/api#setmycommands
+    #[instrument(skip(self, commands))]
+    pub async fn set_my_commands(&self, 
+                                 commands: &[TelegramBotCommand], 
+                                 scope: TelegramBotCommandScope, 
+                                 language_code: Option<&str>) -> Result<(), TelegramBotError>{
+        let mut params = json!({
+            "commands": commands,
+            "scope": scope
+        });
+        if let Some(language_code) = language_code {
+            params["language_code"] = json!(language_code);
+        }
+
+        let _: bool = self
+            .config
+            .call_method("setMyCommands", params)
+            .await?;
+
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        transport::{
+            TelegramFakeTransport
+        }
+    };
+
+    fn client_with_file(file_size: Option<u64>, data: &[u8]) -> TelegramClient {
+        let transport = Arc::new(TelegramFakeTransport::default());
+        transport.push_result("getFile", json!({
+            "file_id": "file_id",
+            "file_unique_id": "file_unique_id",
+            "file_size": file_size,
+            "file_path": "documents/file_1.txt"
+        }));
+        transport.put_file("documents/file_1.txt", data.to_vec());
+        TelegramClient::with_transport(transport)
+    }
+
+    #[tokio::test]
+    async fn test_download_file() {
+        let client = client_with_file(Some(11), b"example.com");
+        let file = client.get_file("file_id").await.unwrap();
+        let data = client.download_file(&file, 1024).await.unwrap();
+        assert_eq!(data, b"example.com");
+    }
+
+    #[tokio::test]
+    async fn test_download_file_too_large() {
+        // Размер из getFile
+        let client = client_with_file(Some(2048), &[0; 2048]);
+        let file = client.get_file("file_id").await.unwrap();
+        assert!(matches!(client.download_file(&file, 1024).await, Err(TelegramBotError::FileTooLarge(1024))));
+
+        // Размер неизвестен, проверяет транспорт
+        let client = client_with_file(None, &[0; 2048]);
+        let file = client.get_file("file_id").await.unwrap();
+        assert!(matches!(client.download_file(&file, 1024).await, Err(TelegramBotError::FileTooLarge(1024))));
+    }
+}
@@ -1,15 +1,126 @@
-use reqwest::{
-    Client
+use std::{
+    sync::{
+        Arc
+    }
 };
-use url::{
-    Url
+use serde::{
+    de::{
+        DeserializeOwned
+    }
 };
-use derive_more::{
-    Constructor
+use serde_json::{
+    Value
+};
+use tracing::{
+    instrument,
+    warn
+};
+use crate::{
+    error::{
+        TelegramBotError
+    },
+    helpers::{
+        DataOrErrorResponse
+    }
+};
+use super::{
+    responses::{
+        TelegramErrorResponse,
+        TelegramResponse
+    },
+    rate_limiter::{
+        TelegramRateLimiter
+    },
+    transport::{
+        TelegramTransport
+    }
 };
 
-#[derive(Debug, Constructor)]
+/// Сколько раз повторяем запрос, если Telegram просит подождать
+const RETRY_AFTER_ATTEMPTS: u32 = 3;
+
+/// Методы, которые отправляют сообщения и расходуют лимит чата
+const CHAT_MESSAGE_METHODS: &[&str] = &[
+    "sendMessage",
+    "sendPhoto",
+    "sendDocument",
+    "sendMediaGroup",
+    "forwardMessage",
+    "copyMessage"
+];
+
+#[derive(Debug)]
 pub struct TelegramClientConfig{
-    pub http_client: Client,
-    pub api_url: Url
-}
\ No newline at end of file
+    pub transport: Arc<dyn TelegramTransport>,
+    rate_limiter: TelegramRateLimiter
+}
+
+impl TelegramClientConfig {
+    pub fn new(transport: Arc<dyn TelegramTransport>) -> TelegramClientConfig{
+        TelegramClientConfig{
+            transport,
+            rate_limiter: Default::default()
+        }
+    }
+
+    /// Вызов произвольного метода API с JSON параметрами.
+    /// Возвращает содержимое поля `result` успешного ответа.
+    /// 
+    /// Все запросы проходят через ограничитель частоты, лимит на чат берется из параметра `chat_id`.
+    /// Если Telegram вернул `retry_after`, то ждем и повторяем запрос.
+    /// Если группа стала супергруппой, то повторяем запрос с новым `chat_id`.
+    #[instrument(skip(self, params))]
+    pub async fn call_method<R>(&self, method: &str, mut params: Value) -> Result<R, TelegramBotError>
+    where
+        R: DeserializeOwned + Send + 'static
+    {
+        let sends_message = CHAT_MESSAGE_METHODS.contains(&method);
+        let mut migrated = false;
+        let mut attempt = 0;
+        loop {
+            let chat_id = params
+                .get("chat_id")
+                .and_then(Value::as_i64);
+
+            self.rate_limiter
+                .acquire(chat_id, sends_message)
+                .await;
+
+            let result = self.execute_request(method, &params).await;
+
+            let (retry_after, migrate_to_chat_id) = match &result {
+                Err(TelegramBotError::ApiError(err)) => (err.retry_after(), err.migrate_to_chat_id()),
+                _ => (None, None)
+            };
+            if let (Some(old_chat_id), Some(new_chat_id), false) = (chat_id, migrate_to_chat_id, migrated) {
+                warn!("Chat {} migrated to {}, repeat method {}", old_chat_id, new_chat_id, method);
+                params["chat_id"] = Value::from(new_chat_id);
+                migrated = true;
+                continue;
+            }
+            match retry_after {
+                Some(retry_after) if attempt < RETRY_AFTER_ATTEMPTS => {
+                    warn!("Telegram rate limit for method {}, retry after {:?}", method, retry_after);
+                    self.rate_limiter.pause_for(chat_id, retry_after);
+                    attempt += 1;
+                },
+                _ => {
+                    return result;
+                }
+            }
+        }
+    }
+
+    async fn execute_request<R>(&self, method: &str, params: &Value) -> Result<R, TelegramBotError>
+    where
+        R: DeserializeOwned + Send + 'static
+    {
+        let response = self
+            .transport
+            .call(method, params)
+            .await?;
+        let response: DataOrErrorResponse<TelegramResponse<R>, TelegramErrorResponse> = serde_json::from_value(response)?;
+
+        Ok(response.into_result()?.result)
+    }
+}
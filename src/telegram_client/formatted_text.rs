@@ -0,0 +1,538 @@
+use serde::{
+    Serialize
+};
+use super::{
+    responses::{
+        TelegramLinkPreviewOptions
+    },
+    reply_markup::{
+        TelegramReplyMarkup
+    }
+};
+
+////////////////////////////////////////////////////////////////////////
+
+/// Максимальная длина текста одного сообщения
+pub const TELEGRAM_MESSAGE_MAX_LENGTH: usize = 4096;
+
+/// Режим разбора разметки текста
+/// https://core.telegram.org/bots/api#formatting-options
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelegramParseMode{
+    #[serde(rename = "HTML")]
+    Html,
+    #[serde(rename = "MarkdownV2")]
+    MarkdownV2
+}
+
+/// Оформление отдельного куска текста
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TelegramTextStyle{
+    Plain,
+    Bold,
+    Italic,
+    Code,
+    Link(String)
+}
+
+#[derive(Debug, Clone)]
+struct TextPart{
+    style: TelegramTextStyle,
+    text: String
+}
+
+////////////////////////////////////////////////////////////////////////
+
+fn escape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            c => result.push(c)
+        }
+    }
+    result
+}
+
+/// Экранирование с помощью `\` указанных символов
+fn escape_markdown_chars(text: &str, special: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\\' || special.contains(c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Обычный текст в MarkdownV2
+fn escape_markdown(text: &str) -> String {
+    escape_markdown_chars(text, "_*[]()~`>#+-=|{}.!")
+}
+
+/// Содержимое `code` в MarkdownV2
+fn escape_markdown_code(text: &str) -> String {
+    escape_markdown_chars(text, "`")
+}
+
+/// Адрес ссылки в MarkdownV2
+fn escape_markdown_url(text: &str) -> String {
+    escape_markdown_chars(text, ")")
+}
+
+/// Итоговый вид куска текста с учетом режима разметки
+fn render_part(parse_mode: Option<TelegramParseMode>, style: &TelegramTextStyle, text: &str) -> String {
+    match parse_mode {
+        None => {
+            match style {
+                TelegramTextStyle::Link(url) if url.as_str() != text => format!("{} ({})", text, url),
+                _ => text.to_string()
+            }
+        },
+        Some(TelegramParseMode::Html) => {
+            let text = escape_html(text);
+            match style {
+                TelegramTextStyle::Plain => text,
+                TelegramTextStyle::Bold => format!("<b>{}</b>", text),
+                TelegramTextStyle::Italic => format!("<i>{}</i>", text),
+                TelegramTextStyle::Code => format!("<code>{}</code>", text),
+                TelegramTextStyle::Link(url) => format!("<a href=\"{}\">{}</a>", escape_html(url), text)
+            }
+        },
+        Some(TelegramParseMode::MarkdownV2) => {
+            match style {
+                TelegramTextStyle::Plain => escape_markdown(text),
+                TelegramTextStyle::Bold => format!("*{}*", escape_markdown(text)),
+                TelegramTextStyle::Italic => format!("_{}_", escape_markdown(text)),
+                TelegramTextStyle::Code => format!("`{}`", escape_markdown_code(text)),
+                TelegramTextStyle::Link(url) => format!("[{}]({})", escape_markdown(text), escape_markdown_url(url))
+            }
+        }
+    }
+}
+
+/// Позиции сразу после каждого вхождения разделителя, то есть возможные места разрыва
+fn boundaries_after(text: &str, separator: &str) -> Vec<usize> {
+    text.match_indices(separator)
+        .map(|(index, _)| index + separator.len())
+        .collect()
+}
+
+/// Позиции между символами
+fn char_boundaries(text: &str) -> Vec<usize> {
+    text.char_indices()
+        .map(|(index, c)| index + c.len_utf8())
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Построитель текста с разметкой. Весь пользовательский текст экранируется
+/// под выбранный режим, поэтому заголовки страниц и прочие внешние данные
+/// можно вставлять как есть.
+#[derive(Debug, Clone)]
+pub struct TelegramFormattedText{
+    parse_mode: Option<TelegramParseMode>,
+    parts: Vec<TextPart>
+}
+
+impl TelegramFormattedText {
+    pub fn new(parse_mode: TelegramParseMode) -> TelegramFormattedText{
+        TelegramFormattedText{
+            parse_mode: Some(parse_mode),
+            parts: Vec::new()
+        }
+    }
+
+    pub fn parse_mode(&self) -> Option<TelegramParseMode>{
+        self.parse_mode
+    }
+
+    pub fn styled(mut self, style: TelegramTextStyle, text: impl Into<String>) -> TelegramFormattedText{
+        self.parts.push(TextPart{
+            style,
+            text: text.into()
+        });
+        self
+    }
+
+    pub fn text(self, text: impl Into<String>) -> TelegramFormattedText{
+        self.styled(TelegramTextStyle::Plain, text)
+    }
+
+    pub fn bold(self, text: impl Into<String>) -> TelegramFormattedText{
+        self.styled(TelegramTextStyle::Bold, text)
+    }
+
+    pub fn italic(self, text: impl Into<String>) -> TelegramFormattedText{
+        self.styled(TelegramTextStyle::Italic, text)
+    }
+
+    pub fn code(self, text: impl Into<String>) -> TelegramFormattedText{
+        self.styled(TelegramTextStyle::Code, text)
+    }
+
+    pub fn link(self, text: impl Into<String>, url: impl Into<String>) -> TelegramFormattedText{
+        self.styled(TelegramTextStyle::Link(url.into()), text)
+    }
+
+    pub fn new_line(self) -> TelegramFormattedText{
+        self.text("\n")
+    }
+
+    /// Готовый текст для поля `text`
+    pub fn render(&self) -> String{
+        self.parts
+            .iter()
+            .map(|part| render_part(self.parse_mode, &part.style, &part.text))
+            .collect()
+    }
+
+    /// Telegram ограничивает длину в UTF-16 после разбора разметки, то есть теги, экранирование
+    /// и адреса ссылок не считаются. Без разметки считаем текст целиком вместе с адресом ссылки.
+    fn part_length(&self, style: &TelegramTextStyle, text: &str) -> usize{
+        match self.parse_mode {
+            Some(_) => text.encode_utf16().count(),
+            None => render_part(None, style, text).encode_utf16().count()
+        }
+    }
+
+    /// Самый длинный префикс текста, который влезает в `room`, из возможных мест разрыва.
+    /// Длина с разметкой растет вместе с префиксом, поэтому ищем бинарным поиском.
+    fn longest_fitting_prefix(&self, style: &TelegramTextStyle, text: &str, room: usize, boundaries: &[usize]) -> Option<usize>{
+        let fitting_count = {
+            let (mut low, mut high) = (0, boundaries.len());
+            while low < high {
+                let middle = (low + high) / 2;
+                if self.part_length(style, &text[..boundaries[middle]]) <= room {
+                    low = middle + 1;
+                }else{
+                    high = middle;
+                }
+            }
+            low
+        };
+        match fitting_count {
+            0 => None,
+            count => Some(boundaries[count - 1])
+        }
+    }
+
+    /// Разбиение на несколько текстов, каждый из которых не длиннее `max_length`.
+    /// Рвем по абзацам, потом по строкам, и только если кусок не влезает даже в пустое
+    /// сообщение - по словам и символам. Оформление куска повторяется в каждой части,
+    /// поэтому теги и разметка никогда не разрываются.
+    pub fn split(&self, max_length: usize) -> Vec<TelegramFormattedText>{
+        let mut chunks = Vec::new();
+        let mut current = TelegramFormattedText{
+            parse_mode: self.parse_mode,
+            parts: Vec::new()
+        };
+        let mut current_length = 0;
+
+        for part in self.parts.iter() {
+            let mut rest: &str = &part.text;
+            while !rest.is_empty() {
+                let room = max_length.saturating_sub(current_length);
+
+                let full_length = self.part_length(&part.style, rest);
+                if full_length <= room {
+                    current.parts.push(TextPart{
+                        style: part.style.clone(),
+                        text: rest.to_string()
+                    });
+                    current_length += full_length;
+                    break;
+                }
+
+                let mut split_at = self.longest_fitting_prefix(&part.style, rest, room, &boundaries_after(rest, "\n\n"))
+                    .or_else(|| self.longest_fitting_prefix(&part.style, rest, room, &boundaries_after(rest, "\n")));
+                if split_at.is_none() && current.parts.is_empty() {
+                    split_at = self.longest_fitting_prefix(&part.style, rest, room, &boundaries_after(rest, " "))
+                        .or_else(|| self.longest_fitting_prefix(&part.style, rest, room, &char_boundaries(rest)))
+                        // Лимит меньше одного символа с разметкой, все равно отдаем хоть что-то
+                        .or_else(|| char_boundaries(rest).first().copied());
+                }
+
+                if let Some(split_at) = split_at {
+                    current.parts.push(TextPart{
+                        style: part.style.clone(),
+                        text: rest[..split_at].to_string()
+                    });
+                    rest = &rest[split_at..];
+                }
+
+                // Текущая часть заполнена
+                let full = std::mem::take(&mut current.parts);
+                chunks.push(TelegramFormattedText{
+                    parse_mode: self.parse_mode,
+                    parts: full
+                });
+                current_length = 0;
+            }
+        }
+
+        if !current.parts.is_empty() {
+            chunks.push(current);
+        }
+
+        chunks
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Текст сообщения вместе с настройками отображения для sendMessage и editMessageText
+#[derive(Serialize, Debug, Clone)]
+pub struct TelegramOutgoingText{
+    pub text: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<TelegramParseMode>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_preview_options: Option<TelegramLinkPreviewOptions>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<TelegramReplyMarkup>
+}
+
+impl TelegramOutgoingText {
+    pub fn with_link_preview(mut self, options: TelegramLinkPreviewOptions) -> TelegramOutgoingText{
+        self.link_preview_options = Some(options);
+        self
+    }
+
+    /// Замена устаревшего `disable_web_page_preview`
+    pub fn without_link_preview(self) -> TelegramOutgoingText{
+        self.with_link_preview(TelegramLinkPreviewOptions::disabled())
+    }
+
+    pub fn with_reply_markup(mut self, reply_markup: impl Into<TelegramReplyMarkup>) -> TelegramOutgoingText{
+        self.reply_markup = Some(reply_markup.into());
+        self
+    }
+}
+
+impl From<String> for TelegramOutgoingText {
+    fn from(text: String) -> Self {
+        TelegramOutgoingText{
+            text,
+            parse_mode: None,
+            link_preview_options: None,
+            reply_markup: None
+        }
+    }
+}
+
+impl From<TelegramFormattedText> for TelegramOutgoingText {
+    fn from(text: TelegramFormattedText) -> Self {
+        TelegramOutgoingText{
+            text: text.render(),
+            parse_mode: text.parse_mode(),
+            link_preview_options: None,
+            reply_markup: None
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Текст части без разметки
+    fn chunk_text(chunk: &TelegramFormattedText) -> String {
+        chunk.parts
+            .iter()
+            .map(|part| part.text.as_str())
+            .collect()
+    }
+
+    fn chunk_length(chunk: &TelegramFormattedText) -> usize {
+        chunk_text(chunk).encode_utf16().count()
+    }
+
+    /// Все `&` в HTML должны начинать целую сущность
+    fn has_broken_html_escape(rendered: &str) -> bool {
+        rendered
+            .match_indices('&')
+            .any(|(index, _)| {
+                let rest = &rendered[index..];
+                !["&amp;", "&lt;", "&gt;", "&quot;"].iter().any(|entity| rest.starts_with(entity))
+            })
+    }
+
+    /// Количество неэкранированных символов в MarkdownV2
+    fn count_unescaped(rendered: &str, symbol: char) -> usize {
+        let mut count = 0;
+        let mut chars = rendered.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                chars.next();
+            } else if c == symbol {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn test_short_text_is_single_chunk() {
+        let text = TelegramFormattedText::new(TelegramParseMode::Html)
+            .bold("Title")
+            .new_line()
+            .text("Body");
+        let chunks = text.split(TELEGRAM_MESSAGE_MAX_LENGTH);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].render(), text.render());
+    }
+
+    #[test]
+    fn test_chunks_fit_message_limit() {
+        let lines: String = (0..2000)
+            .map(|index| format!("Line number {}\n", index))
+            .collect();
+        let text = TelegramFormattedText::new(TelegramParseMode::Html)
+            .text(lines.as_str());
+        let chunks = text.split(TELEGRAM_MESSAGE_MAX_LENGTH);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk_length(chunk) <= TELEGRAM_MESSAGE_MAX_LENGTH));
+        let joined: String = chunks.iter().map(chunk_text).collect();
+        assert_eq!(joined, lines);
+    }
+
+    #[test]
+    fn test_markup_is_not_counted() {
+        // Адрес ссылки и теги не входят в лимит Telegram
+        let url = format!("https://example.com/{}", "a".repeat(5000));
+        let text = TelegramFormattedText::new(TelegramParseMode::Html)
+            .link("Example", url.as_str());
+        assert_eq!(text.split(TELEGRAM_MESSAGE_MAX_LENGTH).len(), 1);
+    }
+
+    #[test]
+    fn test_split_prefers_paragraphs() {
+        let first = format!("{}\n\n", "a".repeat(3000));
+        let rest = format!("{}\n{}", "b".repeat(500), "c".repeat(1000));
+        let text = TelegramFormattedText::new(TelegramParseMode::Html)
+            .text(format!("{}{}", first, rest));
+        let chunks = text.split(TELEGRAM_MESSAGE_MAX_LENGTH);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunk_text(&chunks[0]), first);
+        assert_eq!(chunk_text(&chunks[1]), rest);
+    }
+
+    #[test]
+    fn test_split_falls_back_to_lines() {
+        let first = format!("{}\n", "a".repeat(3000));
+        let rest = "b".repeat(3000);
+        let text = TelegramFormattedText::new(TelegramParseMode::Html)
+            .text(format!("{}{}", first, rest));
+        let chunks = text.split(TELEGRAM_MESSAGE_MAX_LENGTH);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunk_text(&chunks[0]), first);
+        assert_eq!(chunk_text(&chunks[1]), rest);
+    }
+
+    #[test]
+    fn test_html_entities_are_not_broken() {
+        let long_bold = "Tom & Jerry <3 ".repeat(700);
+        let text = TelegramFormattedText::new(TelegramParseMode::Html)
+            .bold(long_bold.as_str())
+            .new_line()
+            .link("Tom & Jerry ".repeat(400), "https://example.com/?a=1&b=2");
+        let chunks = text.split(TELEGRAM_MESSAGE_MAX_LENGTH);
+
+        assert!(chunks.len() > 2);
+        for chunk in chunks.iter() {
+            let rendered = chunk.render();
+            assert!(chunk_length(chunk) <= TELEGRAM_MESSAGE_MAX_LENGTH);
+            assert_eq!(rendered.matches("<b>").count(), rendered.matches("</b>").count());
+            assert_eq!(rendered.matches("<a href=").count(), rendered.matches("</a>").count());
+            assert!(!has_broken_html_escape(&rendered), "Broken escape in: {}", rendered);
+        }
+    }
+
+    #[test]
+    fn test_markdown_entities_are_not_broken() {
+        let text = TelegramFormattedText::new(TelegramParseMode::MarkdownV2)
+            .bold("1. Step! (see docs) ".repeat(300))
+            .new_line()
+            .italic("under_score.value ".repeat(300))
+            .new_line()
+            .code("let a = `b`; ".repeat(400));
+        let chunks = text.split(TELEGRAM_MESSAGE_MAX_LENGTH);
+
+        assert!(chunks.len() > 2);
+        for chunk in chunks.iter() {
+            let rendered = chunk.render();
+            assert!(chunk_length(chunk) <= TELEGRAM_MESSAGE_MAX_LENGTH);
+            assert_eq!(count_unescaped(&rendered, '*') % 2, 0, "Broken bold in: {}", rendered);
+            assert_eq!(count_unescaped(&rendered, '_') % 2, 0, "Broken italic in: {}", rendered);
+            assert_eq!(count_unescaped(&rendered, '`') % 2, 0, "Broken code in: {}", rendered);
+            assert!(!rendered.ends_with('\\') || rendered.ends_with("\\\\"));
+        }
+    }
+
+    #[test]
+    fn test_markdown_escapes_special_chars() {
+        let special = r"_*[]()~`>#+-=|{}.!\\";
+        let text = TelegramFormattedText::new(TelegramParseMode::MarkdownV2)
+            .text(special);
+
+        assert_eq!(text.render(), r"\_\*\[\]\(\)\~\`\>\#\+\-\=\|\{\}\.\!\\\\");
+    }
+
+    #[test]
+    fn test_markdown_styles() {
+        let text = TelegramFormattedText::new(TelegramParseMode::MarkdownV2)
+            .bold("a.b")
+            .italic("c-d")
+            .code(r"e`f\g.h");
+
+        assert_eq!(text.render(), r"*a\.b*_c\-d_`e\`f\\g.h`");
+    }
+
+    #[test]
+    fn test_markdown_link_url_escape() {
+        let text = TelegramFormattedText::new(TelegramParseMode::MarkdownV2)
+            .link("Wiki (en)", r"https://en.wikipedia.org/wiki/Rust_(language)?a=\b");
+
+        assert_eq!(text.render(), r"[Wiki \(en\)](https://en.wikipedia.org/wiki/Rust_(language\)?a=\\b)");
+    }
+
+    #[test]
+    fn test_html_escapes_special_chars() {
+        let text = TelegramFormattedText::new(TelegramParseMode::Html)
+            .text(r#"a & b < c > d "e""#)
+            .new_line()
+            .link("<Tom & Jerry>", r#"https://example.com/?a=1&b="2""#);
+
+        assert_eq!(
+            text.render(),
+            "a &amp; b &lt; c &gt; d &quot;e&quot;\n<a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\">&lt;Tom &amp; Jerry&gt;</a>"
+        );
+    }
+
+    #[test]
+    fn test_plain_text_is_not_escaped() {
+        let text = TelegramFormattedText{
+            parse_mode: None,
+            parts: Vec::new()
+        };
+        let text = text
+            .text("a_b & <c>")
+            .link("Docs", "https://example.com/");
+
+        assert_eq!(text.render(), "a_b & <c>Docs (https://example.com/)");
+    }
+}
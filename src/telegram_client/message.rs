@@ -6,13 +6,30 @@ use std::{
 use derive_more::{
     Constructor
 };
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
 use super::{
     config::{
         TelegramClientConfig
     },
+    client::{
+        TelegramClient
+    },
     responses::{
-        TelegramMessageData
+        TelegramMessageData,
+        TelegramChatId,
+        TelegramMessageId,
+        TelegramReactionTypeData
     },
+    reply_markup::{
+        TelegramInlineKeyboardMarkup
+    },
+    formatted_text::{
+        TelegramOutgoingText
+    }
 };
 
 
@@ -26,6 +43,66 @@ impl TelegramMessage {
     pub fn get_data(&self) -> &TelegramMessageData{
         &self.data
     }
+
+    /// Действия с этим сообщением
+    pub fn handle(&self) -> TelegramMessageHandle{
+        TelegramMessageHandle::new(self.config.clone(), self.data.chat.id, self.data.message_id)
+    }
+}
+
+/// Уже отправленное сообщение, про которое известны только чат и идентификатор,
+/// например сохраненные в базе. Действия с ним не требуют передавать идентификаторы вручную.
+#[derive(Debug, Clone, Constructor)]
+pub struct TelegramMessageHandle{
+    config: Arc<TelegramClientConfig>,
+    chat_id: TelegramChatId,
+    message_id: TelegramMessageId
+}
+
+impl TelegramMessageHandle {
+    fn client(&self) -> TelegramClient{
+        TelegramClient::from_config(self.config.clone())
+    }
+
+    /// Замена текста сообщения, возвращает обновленное сообщение
+    pub async fn edit_text(&self, text: impl Into<TelegramOutgoingText>) -> Result<TelegramMessage, TelegramBotError>{
+        self.client()
+            .edit_text_by_id(self.chat_id, self.message_id, text.into())
+            .await
+    }
+
+    /// Замена клавиатуры, `None` убирает ее
+    pub async fn edit_reply_markup(&self, reply_markup: Option<TelegramInlineKeyboardMarkup>) -> Result<TelegramMessage, TelegramBotError>{
+        self.client()
+            .edit_message_reply_markup(self.chat_id, self.message_id, reply_markup)
+            .await
+    }
+
+    pub async fn delete(&self) -> Result<(), TelegramBotError>{
+        self.client()
+            .delete_message(self.chat_id, self.message_id)
+            .await
+    }
+
+    /// Ответ в тот же чат со ссылкой на это сообщение
+    pub async fn reply(&self, text: impl Into<TelegramOutgoingText>) -> Result<TelegramMessage, TelegramBotError>{
+        self.client()
+            .send_reply(self.chat_id, self.message_id, text.into())
+            .await
+    }
+
+    pub async fn pin(&self, disable_notification: bool) -> Result<(), TelegramBotError>{
+        self.client()
+            .pin_message(self.chat_id, self.message_id, disable_notification)
+            .await
+    }
+
+    /// Реакция бота на сообщение, `None` убирает ее
+    pub async fn react(&self, reaction: Option<TelegramReactionTypeData>) -> Result<(), TelegramBotError>{
+        self.client()
+            .set_message_reaction(self.chat_id, self.message_id, reaction)
+            .await
+    }
 }
 
 impl AsRef<TelegramMessageData> for TelegramMessage{
@@ -39,4 +116,80 @@ impl std::ops::Deref for TelegramMessage{
     fn deref(&self) -> &TelegramMessageData {
         &self.data
     }
-}
\ No newline at end of file
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json
+    };
+    use super::{
+        super::{
+            transport::{
+                TelegramFakeTransport
+            }
+        },
+        *
+    };
+
+    #[tokio::test]
+    async fn test_handle_sends_message_ids() {
+        let transport = Arc::new(TelegramFakeTransport::default());
+        transport.push_result("setMessageReaction", json!(true));
+        transport.push_result("setMessageReaction", json!(true));
+        transport.push_result("pinChatMessage", json!(true));
+        transport.push_result("deleteMessage", json!(true));
+        let handle = TelegramClient::with_transport(transport.clone())
+            .message_handle(-100123, 42);
+
+        handle.react(Some(TelegramReactionTypeData::emoji("👍"))).await.unwrap();
+        handle.react(None).await.unwrap();
+        handle.pin(true).await.unwrap();
+        handle.delete().await.unwrap();
+
+        assert_eq!(transport.calls(), vec![
+            ("setMessageReaction".to_string(), json!({
+                "chat_id": -100123,
+                "message_id": 42,
+                "reaction": [{"type": "emoji", "emoji": "👍"}]
+            })),
+            ("setMessageReaction".to_string(), json!({
+                "chat_id": -100123,
+                "message_id": 42,
+                "reaction": []
+            })),
+            ("pinChatMessage".to_string(), json!({
+                "chat_id": -100123,
+                "message_id": 42,
+                "disable_notification": true
+            })),
+            ("deleteMessage".to_string(), json!({
+                "chat_id": -100123,
+                "message_id": 42
+            }))
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_handle_reply_targets_message() {
+        let transport = Arc::new(TelegramFakeTransport::default());
+        transport.push_result("sendMessage", json!({
+            "message_id": 43,
+            "chat": {"id": 100, "type": "private"},
+            "text": "Saved"
+        }));
+        let handle = TelegramClient::with_transport(transport.clone())
+            .message_handle(100, 42);
+
+        let reply = handle.reply("Saved".to_string()).await.unwrap();
+
+        assert_eq!(reply.message_id, 43);
+        let calls = transport.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "sendMessage");
+        assert_eq!(calls[0].1["chat_id"], json!(100));
+        assert_eq!(calls[0].1["reply_parameters"]["message_id"], json!(42));
+    }
+}
@@ -0,0 +1,316 @@
+use super::{
+    responses::{
+        TelegramMessageData,
+        TelegramMessageEntityData,
+        TelegramMessageOriginData,
+        TelegramChatData,
+        TelegramMessageId
+    }
+};
+
+////////////////////////////////////////////////////////////////////////
+
+/// Постоянная ссылка на пост в канале и имя канала
+#[derive(Debug, Clone)]
+pub struct TelegramChannelPostLink{
+    pub link: String,
+    /// Username канала, а для приватных каналов - название
+    pub channel_name: String
+}
+
+impl TelegramChannelPostLink {
+    /// У публичных каналов ссылка вида `https://t.me/<username>/<id>`,
+    /// у приватных `https://t.me/c/<id без -100>/<id>`, открывается только участникам
+    fn new(chat: &TelegramChatData, message_id: TelegramMessageId) -> Option<TelegramChannelPostLink> {
+        let username = chat
+            .username
+            .as_deref()
+            .filter(|username| !username.is_empty());
+        let link = match username {
+            Some(username) => format!("https://t.me/{}/{}", username, message_id),
+            None => {
+                let id = chat.id.to_string();
+                let internal_id = id.strip_prefix("-100")?;
+                format!("https://t.me/c/{}/{}", internal_id, message_id)
+            }
+        };
+        let channel_name = username
+            .or(chat.title.as_deref())?
+            .to_string();
+        Some(TelegramChannelPostLink{
+            link,
+            channel_name
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Вырезаем кусок текста по смещению и длине в UTF-16 code units, как их отдает Telegram
+fn utf16_substring(text: &str, offset: usize, length: usize) -> Option<String> {
+    let utf16: Vec<u16> = text
+        .encode_utf16()
+        .collect();
+    let end = offset.checked_add(length)?;
+    let slice = utf16.get(offset..end)?;
+    String::from_utf16(slice).ok()
+}
+
+/// Автоматически найденные Telegram ссылки могут быть без схемы: `example.com/page`
+fn normalize_link(link: &str) -> String {
+    let link = link.trim();
+    if link.contains("://") {
+        link.to_string()
+    }else{
+        format!("http://{}", link)
+    }
+}
+
+/// Ссылки из разметки конкретного текста
+fn entities_links(text: Option<&str>, entities: &[TelegramMessageEntityData]) -> Vec<String> {
+    entities
+        .iter()
+        .filter_map(|entity|{
+            match entity.entity_type.as_str() {
+                "text_link" => entity.url.clone(),
+                "url" => text.and_then(|text| utf16_substring(text, entity.offset, entity.length)),
+                _ => None
+            }
+        })
+        .collect()
+}
+
+impl TelegramMessageData {
+    /// Ссылка на исходный пост, если сообщение переслано из канала
+    pub fn forwarded_channel_post(&self) -> Option<TelegramChannelPostLink> {
+        match &self.forward_origin {
+            Some(TelegramMessageOriginData::Channel{chat, message_id}) => {
+                TelegramChannelPostLink::new(chat, *message_id)
+            },
+            Some(_) => None,
+            None => {
+                let chat = self.forward_from_chat.as_ref().filter(|chat| chat.chat_type == "channel")?;
+                TelegramChannelPostLink::new(chat, self.forward_from_message_id?)
+            }
+        }
+    }
+
+    /// Все ссылки, которые содержит сообщение: из текста, скрытые за text_link, из подписи к медиа
+    /// и из превью. Порядок сохраняется, дубликаты и невалидные адреса отбрасываются.
+    pub fn extract_links(&self) -> Vec<String> {
+        let mut candidates: Vec<String> = Vec::new();
+
+        candidates.extend(entities_links(self.text.as_deref(), &self.entities));
+        candidates.extend(entities_links(self.caption.as_deref(), &self.caption_entities));
+
+        if let Some(preview_url) = self.link_preview_options.as_ref().and_then(|o| o.url.as_ref()) {
+            candidates.push(preview_url.clone());
+        }
+
+        let candidates = if !candidates.is_empty() {
+            candidates
+                .iter()
+                .map(|link| normalize_link(link))
+                .collect()
+        }else{
+            // Разметки может не быть, тогда пробуем текст целиком, но уже без дописывания схемы
+            self.text
+                .iter()
+                .chain(self.caption.iter())
+                .map(|text| text.trim().to_string())
+                .collect::<Vec<String>>()
+        };
+
+        let mut links: Vec<String> = Vec::new();
+        for link in candidates.into_iter() {
+            if validator::validate_url(&link) && !links.contains(&link) {
+                links.push(link);
+            }
+        }
+        links
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json,
+        Value
+    };
+    use super::*;
+
+    /// Сообщение в личном чате с указанными полями
+    fn message(fields: Value) -> TelegramMessageData {
+        let mut value = json!({
+            "message_id": 1,
+            "chat": {"id": 100, "type": "private"}
+        });
+        for (key, field) in fields.as_object().unwrap() {
+            value[key] = field.clone();
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_url_offsets_after_emoji(){
+        let message = message(json!({
+            "text": "👍👍 look https://example.com/page done",
+            "entities": [
+                {"type": "url", "offset": 10, "length": 24}
+            ]
+        }));
+
+        assert_eq!(message.extract_links(), vec!["https://example.com/page".to_string()]);
+    }
+
+    #[test]
+    fn test_url_offsets_after_cyrillic(){
+        let message = message(json!({
+            "text": "Смотри example.org/page",
+            "entities": [
+                {"type": "url", "offset": 7, "length": 16}
+            ]
+        }));
+
+        assert_eq!(message.extract_links(), vec!["http://example.org/page".to_string()]);
+    }
+
+    #[test]
+    fn test_text_link_and_url(){
+        let message = message(json!({
+            "text": "читать тут и https://b.com",
+            "entities": [
+                {"type": "bold", "offset": 0, "length": 6},
+                {"type": "text_link", "offset": 7, "length": 3, "url": "https://a.com/hidden"},
+                {"type": "url", "offset": 13, "length": 13}
+            ]
+        }));
+
+        assert_eq!(message.extract_links(), vec![
+            "https://a.com/hidden".to_string(),
+            "https://b.com".to_string()
+        ]);
+    }
+
+    #[test]
+    fn test_caption_entities(){
+        let message = message(json!({
+            "caption": "Фото: https://c.com",
+            "caption_entities": [
+                {"type": "url", "offset": 6, "length": 13}
+            ]
+        }));
+
+        assert_eq!(message.extract_links(), vec!["https://c.com".to_string()]);
+    }
+
+    #[test]
+    fn test_links_are_deduplicated(){
+        let message = message(json!({
+            "text": "example.com и http://example.com",
+            "entities": [
+                {"type": "url", "offset": 0, "length": 11},
+                {"type": "url", "offset": 14, "length": 18}
+            ],
+            "link_preview_options": {"url": "http://example.com"}
+        }));
+
+        assert_eq!(message.extract_links(), vec!["http://example.com".to_string()]);
+    }
+
+    #[test]
+    fn test_text_without_entities(){
+        let link = message(json!({
+            "text": " https://d.com "
+        }));
+        assert_eq!(link.extract_links(), vec!["https://d.com".to_string()]);
+
+        let plain = message(json!({
+            "text": "просто текст"
+        }));
+        assert!(plain.extract_links().is_empty());
+    }
+
+    #[test]
+    fn test_entity_out_of_range_falls_back_to_text(){
+        let message = message(json!({
+            "text": "https://e.com",
+            "entities": [
+                {"type": "url", "offset": 5, "length": 100}
+            ]
+        }));
+
+        assert_eq!(message.extract_links(), vec!["https://e.com".to_string()]);
+    }
+
+    #[test]
+    fn test_public_channel_post(){
+        let message = message(json!({
+            "forward_origin": {
+                "type": "channel",
+                "chat": {"id": -1001234567890i64, "type": "channel", "title": "News", "username": "news"},
+                "message_id": 55,
+                "date": 1700000000
+            }
+        }));
+
+        let post = message.forwarded_channel_post().unwrap();
+        assert_eq!(post.link, "https://t.me/news/55");
+        assert_eq!(post.channel_name, "news");
+    }
+
+    #[test]
+    fn test_private_channel_post(){
+        let message = message(json!({
+            "forward_origin": {
+                "type": "channel",
+                "chat": {"id": -1001234567890i64, "type": "channel", "title": "Private news"},
+                "message_id": 56,
+                "date": 1700000000
+            }
+        }));
+
+        let post = message.forwarded_channel_post().unwrap();
+        assert_eq!(post.link, "https://t.me/c/1234567890/56");
+        assert_eq!(post.channel_name, "Private news");
+    }
+
+    #[test]
+    fn test_forward_from_user_is_not_channel_post(){
+        let message = message(json!({
+            "forward_origin": {
+                "type": "user",
+                "sender_user": {"id": 7, "is_bot": false, "first_name": "User"},
+                "date": 1700000000
+            },
+            "forward_from_chat": {"id": -1001234567890i64, "type": "channel", "username": "news"},
+            "forward_from_message_id": 57
+        }));
+
+        assert!(message.forwarded_channel_post().is_none());
+    }
+
+    #[test]
+    fn test_legacy_forward_fields(){
+        let channel = message(json!({
+            "forward_from_chat": {"id": -1001234567890i64, "type": "channel", "username": "news"},
+            "forward_from_message_id": 58
+        }));
+        let post = channel.forwarded_channel_post().unwrap();
+        assert_eq!(post.link, "https://t.me/news/58");
+        assert_eq!(post.channel_name, "news");
+
+        let group = message(json!({
+            "forward_from_chat": {"id": -1001234567890i64, "type": "supergroup", "username": "chat"},
+            "forward_from_message_id": 59
+        }));
+        assert!(group.forwarded_channel_post().is_none());
+
+        let not_forwarded = message(json!({
+            "text": "https://example.com"
+        }));
+        assert!(not_forwarded.forwarded_channel_post().is_none());
+    }
+}
@@ -2,19 +2,83 @@ mod message;
 mod config;
 mod client;
 mod responses;
+mod updates;
+mod message_links;
+mod reply_markup;
+mod rate_limiter;
+mod formatted_text;
+mod bot_commands;
+mod bot_identity;
+mod chat_action;
+mod transport;
 
 pub use {
     client::{
         TelegramClient
     },
-    message::{
-        TelegramMessage
-    },
     responses::{
         TelegramErrorResponse,
+        TelegramChatId,
         TelegramUserId,
-        TelegramUpdatesResponse,
         TelegramMessageData,
-        TelegramMessageId
+        TelegramMessageId,
+        TelegramDocumentData,
+        TelegramLinkPreviewOptions,
+        TelegramCallbackQueryData,
+        TelegramInlineQueryData,
+        TelegramChosenInlineResultData,
+        TelegramInlineQueryResultArticle,
+        TelegramInlineQueryResultsButton,
+        TelegramReactionTypeData
+    },
+    updates::{
+        TelegramUpdateData,
+        TelegramUpdateKind
+    },
+    reply_markup::{
+        TelegramReplyMarkup,
+        TelegramInlineKeyboardMarkup,
+        TelegramInlineKeyboardButton,
+        TelegramReplyKeyboardMarkup,
+        TelegramKeyboardButton,
+        TelegramReplyKeyboardRemove
+    },
+    formatted_text::{
+        TelegramParseMode,
+        TelegramFormattedText,
+        TelegramOutgoingText
+    },
+    bot_commands::{
+        TelegramBotCommand,
+        TelegramBotCommandScope
+    },
+    bot_identity::{
+        TelegramBotIdentity
+    },
+    chat_action::{
+        TelegramChatAction
+    },
+    transport::{
+        TelegramTransport,
+        TelegramHttpTransport
+    }
+};
+// Обработчики создают кнопки через конструкторы, но тип действия нужен для разбора готовых клавиатур
+#[allow(unused_imports)]
+pub use {
+    reply_markup::{
+        TelegramInlineKeyboardButtonAction
+    }
+};
+#[cfg(any(test, feature = "telegram_fixtures"))]
+pub use {
+    transport::{
+        TelegramRecordReplayTransport
+    }
+};
+#[cfg(test)]
+pub use {
+    transport::{
+        TelegramFakeTransport
     }
 };
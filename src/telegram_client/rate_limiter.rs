@@ -0,0 +1,324 @@
+use std::{
+    sync::{
+        Mutex
+    },
+    collections::{
+        HashMap
+    },
+    time::{
+        Duration,
+        Instant
+    }
+};
+use tracing::{
+    instrument,
+    trace
+};
+use super::{
+    responses::{
+        TelegramChatId
+    }
+};
+
+////////////////////////////////////////////////////////////////////////
+
+/// Общий лимит Telegram на все исходящие запросы бота
+const GLOBAL_REQUESTS_PER_SECOND: f64 = 30.0;
+
+/// В личный чат не стоит писать чаще одного раза в секунду, короткие всплески допустимы
+const PRIVATE_CHAT_BURST: f64 = 3.0;
+const PRIVATE_CHAT_REQUESTS_PER_SECOND: f64 = 1.0;
+
+/// В группы можно писать не больше 20 сообщений в минуту
+const GROUP_CHAT_BURST: f64 = 3.0;
+const GROUP_CHAT_REQUESTS_PER_SECOND: f64 = 20.0 / 60.0;
+
+/// После скольких чатов начинаем выкидывать неактивные бакеты
+const CHAT_BUCKETS_CLEANUP_THRESHOLD: usize = 10_000;
+
+////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+struct TokenBucket{
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    updated: Instant,
+    /// Telegram попросил подождать через `retry_after`
+    paused_until: Option<Instant>
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, refill_per_second: f64, now: Instant) -> TokenBucket{
+        TokenBucket{
+            capacity,
+            tokens: capacity,
+            refill_per_second,
+            updated: now,
+            paused_until: None
+        }
+    }
+
+    /// Для групп и каналов идентификаторы чатов отрицательные
+    fn for_chat(chat_id: TelegramChatId, now: Instant) -> TokenBucket{
+        if chat_id < 0 {
+            TokenBucket::new(GROUP_CHAT_BURST, GROUP_CHAT_REQUESTS_PER_SECOND, now)
+        }else{
+            TokenBucket::new(PRIVATE_CHAT_BURST, PRIVATE_CHAT_REQUESTS_PER_SECOND, now)
+        }
+    }
+
+    fn refill(&mut self, now: Instant){
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Сколько ждать до появления целого токена
+    fn wait_time(&self) -> Duration{
+        if self.tokens >= 1.0 {
+            Duration::from_secs(0)
+        }else{
+            Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_second)
+        }
+    }
+
+    fn pause_until(&mut self, until: Instant){
+        self.paused_until = Some(self.paused_until.map_or(until, |prev| prev.max(until)));
+    }
+
+    /// Сколько осталось ждать после `retry_after`, истекшая пауза сбрасывается
+    fn pause_time(&mut self, now: Instant) -> Duration{
+        match self.paused_until {
+            Some(paused_until) if paused_until > now => paused_until - now,
+            _ => {
+                self.paused_until = None;
+                Duration::from_secs(0)
+            }
+        }
+    }
+
+    /// Бакет можно выкинуть, если он полон и не на паузе, новый будет таким же
+    fn is_idle(&self) -> bool{
+        self.tokens >= self.capacity && self.paused_until.is_none()
+    }
+}
+
+#[derive(Debug)]
+struct RateLimiterState{
+    global: TokenBucket,
+    chats: HashMap<TelegramChatId, TokenBucket>
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Ограничитель частоты запросов к API на основе token bucket: общий лимит на бота и отдельный на каждый чат.
+/// Лимит чата расходуют только сообщения, а пауза по `retry_after` для чата действует на все его запросы.
+#[derive(Debug)]
+pub struct TelegramRateLimiter{
+    state: Mutex<RateLimiterState>
+}
+
+impl Default for TelegramRateLimiter {
+    fn default() -> Self {
+        TelegramRateLimiter::started_at(Instant::now())
+    }
+}
+
+impl TelegramRateLimiter {
+    fn started_at(now: Instant) -> TelegramRateLimiter{
+        TelegramRateLimiter{
+            state: Mutex::new(RateLimiterState{
+                global: TokenBucket::new(GLOBAL_REQUESTS_PER_SECOND, GLOBAL_REQUESTS_PER_SECOND, now),
+                chats: HashMap::new()
+            })
+        }
+    }
+
+    /// Ждем, пока можно будет выполнить запрос, и забираем под него токены.
+    /// `sends_message` - запрос отправляет сообщение в чат и расходует лимит этого чата.
+    #[instrument(skip(self))]
+    pub async fn acquire(&self, chat_id: Option<TelegramChatId>, sends_message: bool){
+        loop {
+            let wait = self.try_acquire(chat_id, sends_message, Instant::now());
+            if wait == Duration::from_secs(0) {
+                return;
+            }
+            trace!("Rate limit wait: {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Telegram вернул `retry_after`: приостанавливаем запросы в этот чат,
+    /// а если чат неизвестен, то все запросы бота
+    #[instrument(skip(self))]
+    pub fn pause_for(&self, chat_id: Option<TelegramChatId>, duration: Duration){
+        self.pause_until(chat_id, Instant::now() + duration);
+    }
+
+    fn pause_until(&self, chat_id: Option<TelegramChatId>, until: Instant){
+        let mut state = self.state.lock().expect("Mutex lock failed");
+        match chat_id {
+            Some(chat_id) => {
+                state
+                    .chats
+                    .entry(chat_id)
+                    .or_insert_with(|| TokenBucket::for_chat(chat_id, until))
+                    .pause_until(until);
+            },
+            None => {
+                state.global.pause_until(until);
+            }
+        }
+    }
+
+    /// Возвращает нулевое время ожидания, если токены удалось забрать
+    fn try_acquire(&self, chat_id: Option<TelegramChatId>, sends_message: bool, now: Instant) -> Duration{
+        let mut state = self.state.lock().expect("Mutex lock failed");
+        let state = &mut *state;
+
+        state.global.refill(now);
+        let mut wait = state.global.pause_time(now).max(state.global.wait_time());
+
+        if state.chats.len() > CHAT_BUCKETS_CLEANUP_THRESHOLD {
+            state.chats.retain(|_, bucket|{
+                bucket.refill(now);
+                bucket.pause_time(now);
+                !bucket.is_idle()
+            });
+        }
+
+        if let Some(chat_id) = chat_id {
+            let chat_bucket = state
+                .chats
+                .entry(chat_id)
+                .or_insert_with(|| TokenBucket::for_chat(chat_id, now));
+            wait = wait.max(chat_bucket.pause_time(now));
+            if sends_message {
+                chat_bucket.refill(now);
+                wait = wait.max(chat_bucket.wait_time());
+                if wait == Duration::from_secs(0) {
+                    chat_bucket.tokens -= 1.0;
+                }
+            }
+        }
+
+        if wait == Duration::from_secs(0) {
+            state.global.tokens -= 1.0;
+        }
+
+        wait
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const PRIVATE_CHAT: TelegramChatId = 1000;
+    const OTHER_PRIVATE_CHAT: TelegramChatId = 2000;
+    const GROUP_CHAT: TelegramChatId = -1001000;
+
+    fn secs(value: f64) -> Duration{
+        Duration::from_secs_f64(value)
+    }
+
+    fn assert_wait(actual: Duration, expected: Duration){
+        let diff = (actual.as_secs_f64() - expected.as_secs_f64()).abs();
+        assert!(diff < 0.001, "wait {:?}, expected {:?}", actual, expected);
+    }
+
+    #[test]
+    fn test_chat_burst(){
+        let now = Instant::now();
+        let limiter = TelegramRateLimiter::started_at(now);
+
+        for _ in 0..3 {
+            assert_eq!(limiter.try_acquire(Some(PRIVATE_CHAT), true, now), Duration::from_secs(0));
+        }
+        assert_wait(limiter.try_acquire(Some(PRIVATE_CHAT), true, now), secs(1.0));
+
+        for _ in 0..3 {
+            assert_eq!(limiter.try_acquire(Some(GROUP_CHAT), true, now), Duration::from_secs(0));
+        }
+        assert_wait(limiter.try_acquire(Some(GROUP_CHAT), true, now), secs(3.0));
+    }
+
+    #[test]
+    fn test_chat_refill(){
+        let now = Instant::now();
+        let limiter = TelegramRateLimiter::started_at(now);
+
+        for _ in 0..3 {
+            limiter.try_acquire(Some(PRIVATE_CHAT), true, now);
+        }
+        assert_wait(limiter.try_acquire(Some(PRIVATE_CHAT), true, now + secs(0.5)), secs(0.5));
+        assert_eq!(limiter.try_acquire(Some(PRIVATE_CHAT), true, now + secs(1.0)), Duration::from_secs(0));
+        assert_wait(limiter.try_acquire(Some(PRIVATE_CHAT), true, now + secs(1.0)), secs(1.0));
+    }
+
+    #[test]
+    fn test_global_burst_and_refill(){
+        let now = Instant::now();
+        let limiter = TelegramRateLimiter::started_at(now);
+
+        for _ in 0..30 {
+            assert_eq!(limiter.try_acquire(None, false, now), Duration::from_secs(0));
+        }
+        assert_wait(limiter.try_acquire(None, false, now), secs(1.0 / 30.0));
+        assert_eq!(limiter.try_acquire(None, false, now + secs(0.1)), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_chats_are_isolated(){
+        let now = Instant::now();
+        let limiter = TelegramRateLimiter::started_at(now);
+
+        for _ in 0..3 {
+            limiter.try_acquire(Some(PRIVATE_CHAT), true, now);
+        }
+        assert!(limiter.try_acquire(Some(PRIVATE_CHAT), true, now) > Duration::from_secs(0));
+        assert_eq!(limiter.try_acquire(Some(OTHER_PRIVATE_CHAT), true, now), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_only_messages_charge_chat(){
+        let now = Instant::now();
+        let limiter = TelegramRateLimiter::started_at(now);
+
+        for _ in 0..10 {
+            assert_eq!(limiter.try_acquire(Some(PRIVATE_CHAT), false, now), Duration::from_secs(0));
+        }
+        for _ in 0..3 {
+            assert_eq!(limiter.try_acquire(Some(PRIVATE_CHAT), true, now), Duration::from_secs(0));
+        }
+        assert!(limiter.try_acquire(Some(PRIVATE_CHAT), true, now) > Duration::from_secs(0));
+        assert_eq!(limiter.try_acquire(Some(PRIVATE_CHAT), false, now), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_chat_pause(){
+        let now = Instant::now();
+        let limiter = TelegramRateLimiter::started_at(now);
+
+        limiter.pause_until(Some(PRIVATE_CHAT), now + secs(5.0));
+
+        assert_wait(limiter.try_acquire(Some(PRIVATE_CHAT), true, now), secs(5.0));
+        assert_wait(limiter.try_acquire(Some(PRIVATE_CHAT), false, now + secs(2.0)), secs(3.0));
+        assert_eq!(limiter.try_acquire(Some(OTHER_PRIVATE_CHAT), true, now), Duration::from_secs(0));
+        assert_eq!(limiter.try_acquire(None, false, now), Duration::from_secs(0));
+        assert_eq!(limiter.try_acquire(Some(PRIVATE_CHAT), true, now + secs(5.0)), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_global_pause(){
+        let now = Instant::now();
+        let limiter = TelegramRateLimiter::started_at(now);
+
+        limiter.pause_until(None, now + secs(5.0));
+
+        assert_wait(limiter.try_acquire(None, false, now), secs(5.0));
+        assert_wait(limiter.try_acquire(Some(PRIVATE_CHAT), true, now), secs(5.0));
+        assert_eq!(limiter.try_acquire(Some(PRIVATE_CHAT), true, now + secs(5.0)), Duration::from_secs(0));
+    }
+}
@@ -0,0 +1,230 @@
+use serde::{
+    Serialize,
+    Deserialize
+};
+
+////////////////////////////////////////////////////////////////////////
+
+/// Любой из вариантов `reply_markup`, который можно прикрепить к сообщению
+/// https://core.telegram.org/bots/api#sendmessage
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum TelegramReplyMarkup{
+    InlineKeyboard(TelegramInlineKeyboardMarkup),
+    ReplyKeyboard(TelegramReplyKeyboardMarkup),
+    ReplyKeyboardRemove(TelegramReplyKeyboardRemove)
+}
+
+impl From<TelegramInlineKeyboardMarkup> for TelegramReplyMarkup{
+    fn from(markup: TelegramInlineKeyboardMarkup) -> Self {
+        TelegramReplyMarkup::InlineKeyboard(markup)
+    }
+}
+
+impl From<TelegramReplyKeyboardMarkup> for TelegramReplyMarkup{
+    fn from(markup: TelegramReplyKeyboardMarkup) -> Self {
+        TelegramReplyMarkup::ReplyKeyboard(markup)
+    }
+}
+
+impl From<TelegramReplyKeyboardRemove> for TelegramReplyMarkup{
+    fn from(markup: TelegramReplyKeyboardRemove) -> Self {
+        TelegramReplyMarkup::ReplyKeyboardRemove(markup)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Кнопки прямо под сообщением
+/// https://core.telegram.org/bots/api#inlinekeyboardmarkup
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TelegramInlineKeyboardMarkup{
+    pub inline_keyboard: Vec<Vec<TelegramInlineKeyboardButton>>
+}
+
+impl TelegramInlineKeyboardMarkup {
+    pub fn new(rows: Vec<Vec<TelegramInlineKeyboardButton>>) -> TelegramInlineKeyboardMarkup{
+        TelegramInlineKeyboardMarkup{
+            inline_keyboard: rows
+        }
+    }
+
+    /// Все кнопки в одну строку
+    pub fn single_row(buttons: Vec<TelegramInlineKeyboardButton>) -> TelegramInlineKeyboardMarkup{
+        TelegramInlineKeyboardMarkup::new(vec![buttons])
+    }
+}
+
+/// https://core.telegram.org/bots/api#inlinekeyboardbutton
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TelegramInlineKeyboardButton{
+    pub text: String,
+
+    #[serde(flatten)]
+    pub action: TelegramInlineKeyboardButtonAction
+}
+
+impl TelegramInlineKeyboardButton {
+    /// Кнопка, открывающая ссылку
+    pub fn url(text: impl Into<String>, url: impl Into<String>) -> TelegramInlineKeyboardButton{
+        TelegramInlineKeyboardButton{
+            text: text.into(),
+            action: TelegramInlineKeyboardButtonAction::Url(url.into())
+        }
+    }
+
+    /// Кнопка, присылающая боту `callback_query` с указанными данными.
+    /// Telegram ограничивает данные 64 байтами.
+    pub fn callback(text: impl Into<String>, data: impl Into<String>) -> TelegramInlineKeyboardButton{
+        TelegramInlineKeyboardButton{
+            text: text.into(),
+            action: TelegramInlineKeyboardButtonAction::CallbackData(data.into())
+        }
+    }
+}
+
+/// Что делает кнопка, сериализуется как одно поле рядом с `text`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TelegramInlineKeyboardButtonAction{
+    Url(String),
+    CallbackData(String)
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Кастомная клавиатура вместо системной
+/// https://core.telegram.org/bots/api#replykeyboardmarkup
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TelegramReplyKeyboardMarkup{
+    pub keyboard: Vec<Vec<TelegramKeyboardButton>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resize_keyboard: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub one_time_keyboard: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_field_placeholder: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selective: Option<bool>
+}
+
+impl TelegramReplyKeyboardMarkup {
+    /// Клавиатура подгоняется по высоте под кнопки
+    pub fn new(rows: Vec<Vec<TelegramKeyboardButton>>) -> TelegramReplyKeyboardMarkup{
+        TelegramReplyKeyboardMarkup{
+            keyboard: rows,
+            resize_keyboard: Some(true),
+            one_time_keyboard: None,
+            input_field_placeholder: None,
+            selective: None
+        }
+    }
+}
+
+/// https://core.telegram.org/bots/api#keyboardbutton
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TelegramKeyboardButton{
+    pub text: String
+}
+
+impl TelegramKeyboardButton {
+    /// Нажатие отправляет текст кнопки как обычное сообщение
+    pub fn new(text: impl Into<String>) -> TelegramKeyboardButton{
+        TelegramKeyboardButton{
+            text: text.into()
+        }
+    }
+}
+
+/// Убирает ранее показанную кастомную клавиатуру
+/// https://core.telegram.org/bots/api#replykeyboardremove
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TelegramReplyKeyboardRemove{
+    /// Всегда `true`, так требует API
+    pub remove_keyboard: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selective: Option<bool>
+}
+
+impl Default for TelegramReplyKeyboardRemove {
+    fn default() -> Self {
+        TelegramReplyKeyboardRemove{
+            remove_keyboard: true,
+            selective: None
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json
+    };
+    use super::*;
+
+    #[test]
+    fn test_inline_keyboard(){
+        let markup = TelegramReplyMarkup::from(TelegramInlineKeyboardMarkup::new(vec![
+            vec![
+                TelegramInlineKeyboardButton::url("Open", "https://example.com"),
+                TelegramInlineKeyboardButton::callback("Archive", "a:1")
+            ]
+        ]));
+
+        assert_eq!(serde_json::to_value(&markup).unwrap(), json!({
+            "inline_keyboard": [[
+                {"text": "Open", "url": "https://example.com"},
+                {"text": "Archive", "callback_data": "a:1"}
+            ]]
+        }));
+    }
+
+    #[test]
+    fn test_reply_keyboard(){
+        let markup = TelegramReplyMarkup::from(TelegramReplyKeyboardMarkup::new(vec![
+            vec![TelegramKeyboardButton::new("/start")]
+        ]));
+        assert_eq!(serde_json::to_value(&markup).unwrap(), json!({
+            "keyboard": [[{"text": "/start"}]],
+            "resize_keyboard": true
+        }));
+
+        let markup = TelegramReplyMarkup::from(TelegramReplyKeyboardMarkup{
+            one_time_keyboard: Some(true),
+            input_field_placeholder: Some("Link".to_string()),
+            selective: Some(false),
+            ..TelegramReplyKeyboardMarkup::new(Vec::new())
+        });
+        assert_eq!(serde_json::to_value(&markup).unwrap(), json!({
+            "keyboard": [],
+            "resize_keyboard": true,
+            "one_time_keyboard": true,
+            "input_field_placeholder": "Link",
+            "selective": false
+        }));
+    }
+
+    #[test]
+    fn test_reply_keyboard_remove(){
+        let markup = TelegramReplyMarkup::from(TelegramReplyKeyboardRemove::default());
+        assert_eq!(serde_json::to_value(&markup).unwrap(), json!({
+            "remove_keyboard": true
+        }));
+
+        let markup = TelegramReplyMarkup::from(TelegramReplyKeyboardRemove{
+            selective: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(serde_json::to_value(&markup).unwrap(), json!({
+            "remove_keyboard": true,
+            "selective": true
+        }));
+    }
+}
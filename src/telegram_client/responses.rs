@@ -1,64 +1,368 @@
 use std::{
     collections::{
         HashMap
+    },
+    time::{
+        Duration
     }
 };
 use serde::{
+    Serialize,
     Deserialize
 };
 use serde_json::{
     Value
 };
+use super::{
+    formatted_text::{
+        TelegramParseMode,
+        TelegramOutgoingText
+    }
+};
 
 ////////////////////////////////////////////////////////////////////////
 
 #[derive(Deserialize, Debug)]
 pub struct TelegramErrorResponse{
-    pub ok: bool,
     pub error_code: i32,
-    pub description: String
+    pub description: String,
+    pub parameters: Option<TelegramResponseParameters>
 }
 
-////////////////////////////////////////////////////////////////////////
+impl TelegramErrorResponse {
+    /// Сколько Telegram просит подождать перед повтором запроса при ошибке 429
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.parameters
+            .as_ref()
+            .and_then(|p| p.retry_after)
+            .map(Duration::from_secs)
+    }
 
-#[derive(Deserialize, Debug)]
-pub struct TelegramUpdatesResponse{
-    pub ok: bool,
-    pub result: Vec<TelegramUpdateData>,
+    /// Новый идентификатор чата, если группа была преобразована в супергруппу
+    pub fn migrate_to_chat_id(&self) -> Option<TelegramChatId> {
+        self.parameters
+            .as_ref()
+            .and_then(|p| p.migrate_to_chat_id)
+    }
 
-    #[serde(flatten)]
-    pub other: HashMap<String, Value>
+    /// Правка сообщения без изменений, например при повторном нажатии на кнопку.
+    /// Для нас это не ошибка, сообщение и так в нужном виде.
+    pub fn is_not_modified(&self) -> bool {
+        self.error_code == 400 && self.description.contains("message is not modified")
+    }
 }
 
+/// Дополнительная информация об ошибке
+/// https://core.telegram.org/bots/api#responseparameters
 #[derive(Deserialize, Debug)]
-pub struct TelegramMessageResponse{
-    pub ok: bool,
-    pub result: TelegramMessageData,
+pub struct TelegramResponseParameters{
+    pub migrate_to_chat_id: Option<TelegramChatId>,
+    pub retry_after: Option<u64>
+}
 
-    #[serde(flatten)]
-    pub other: HashMap<String, Value>
+////////////////////////////////////////////////////////////////////////
+
+/// Общий вид успешного ответа для методов, где нужен только `result`
+#[derive(Deserialize, Debug)]
+pub struct TelegramResponse<T>{
+    pub result: T
 }
 
 ////////////////////////////////////////////////////////////////////////
 
 pub type TelegramUserId = i64;
+/// В личных чатах совпадает с идентификатором пользователя
+pub type TelegramChatId = i64;
 pub type TelegramMessageId = i64;
 
-#[derive(Deserialize, Debug)]
-pub struct TelegramUpdateData{
-    pub update_id: i64,
-    pub message: Option<TelegramMessageData>
-}
-
 #[derive(Deserialize, Debug)]
 pub struct TelegramMessageData{
     pub message_id: TelegramMessageId,
     pub from: Option<TelegramUserData>,
-    pub text: Option<String>
+    pub chat: TelegramChatData,
+    pub text: Option<String>,
+    /// Разметка текста: ссылки, упоминания, форматирование
+    #[serde(default)]
+    pub entities: Vec<TelegramMessageEntityData>,
+    /// Подпись у фото, видео, документов
+    pub caption: Option<String>,
+    #[serde(default)]
+    pub caption_entities: Vec<TelegramMessageEntityData>,
+    pub link_preview_options: Option<TelegramLinkPreviewOptions>,
+    /// Файл, отправленный как документ
+    pub document: Option<TelegramDocumentData>,
+    /// Сообщение, на которое отвечают
+    pub reply_to_message: Option<Box<TelegramMessageData>>,
+    /// Служебное сообщение: группа преобразована в супергруппу с новым идентификатором
+    pub migrate_to_chat_id: Option<TelegramChatId>,
+    /// Откуда переслано сообщение
+    pub forward_origin: Option<TelegramMessageOriginData>,
+    /// Устаревшие поля пересылки, старые версии Bot API присылают только их
+    pub forward_from_chat: Option<TelegramChatData>,
+    pub forward_from_message_id: Option<TelegramMessageId>
+}
+
+/// Источник пересланного сообщения, нужны только посты каналов,
+/// пересылки от пользователей и групп попадают в `Unknown`
+/// https://core.telegram.org/bots/api#messageorigin
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TelegramMessageOriginData{
+    Channel{
+        chat: TelegramChatData,
+        message_id: TelegramMessageId
+    },
+    #[serde(other)]
+    Unknown
+}
+
+/// https://core.telegram.org/bots/api#document
+#[derive(Deserialize, Debug, Clone)]
+pub struct TelegramDocumentData{
+    /// Идентификатор для getFile
+    pub file_id: String,
+    pub file_name: Option<String>,
+    pub mime_type: Option<String>,
+    pub file_size: Option<u64>
+}
+
+/// Файл, готовый к скачиванию, ссылка действует минимум час
+/// https://core.telegram.org/bots/api#file
+#[derive(Deserialize, Debug, Clone)]
+pub struct TelegramFileData{
+    pub file_size: Option<u64>,
+    /// Путь относительно адреса для скачивания файлов
+    pub file_path: Option<String>
+}
+
+/// Смещение и длина указываются в UTF-16 code units
+/// https://core.telegram.org/bots/api#messageentity
+#[derive(Deserialize, Debug)]
+pub struct TelegramMessageEntityData{
+    /// url, text_link, bold, mention и тд
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub offset: usize,
+    pub length: usize,
+    /// Только для text_link
+    pub url: Option<String>
+}
+
+/// Настройки превью ссылки, используются и во входящих, и в исходящих сообщениях
+/// https://core.telegram.org/bots/api#linkpreviewoptions
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TelegramLinkPreviewOptions{
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_disabled: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefer_small_media: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefer_large_media: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_above_text: Option<bool>
+}
+
+impl TelegramLinkPreviewOptions {
+    /// Без превью ссылок
+    pub fn disabled() -> TelegramLinkPreviewOptions{
+        TelegramLinkPreviewOptions{
+            is_disabled: Some(true),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
 pub struct TelegramUserData{
     pub id: TelegramUserId,
+    #[allow(dead_code)] // Пока не читается, но есть в каждом обновлении
     pub username: Option<String>
+}
+
+/// https://core.telegram.org/bots/api#chat
+#[derive(Deserialize, Debug)]
+pub struct TelegramChatData{
+    pub id: TelegramChatId,
+    /// private, group, supergroup или channel
+    #[serde(rename = "type")]
+    pub chat_type: String,
+    pub title: Option<String>,
+    pub username: Option<String>
+}
+
+impl TelegramChatData {
+    pub fn is_private(&self) -> bool {
+        self.chat_type == "private"
+    }
+}
+
+/// Нажатие на inline кнопку
+/// https://core.telegram.org/bots/api#callbackquery
+#[derive(Deserialize, Debug)]
+pub struct TelegramCallbackQueryData{
+    pub id: String,
+    pub from: TelegramUserData,
+    /// Сообщение с кнопкой, может отсутствовать если сообщение слишком старое
+    pub message: Option<TelegramMessageData>,
+    pub data: Option<String>
+}
+
+/// Запрос в inline режиме: `@bot текст` в любом чате
+/// https://core.telegram.org/bots/api#inlinequery
+#[derive(Deserialize, Debug)]
+pub struct TelegramInlineQueryData{
+    pub id: String,
+    pub from: TelegramUserData,
+    pub query: String,
+    pub offset: String,
+    /// Тип чата, из которого пришел запрос
+    #[allow(dead_code)] // Пока отвечаем одинаково для всех чатов
+    pub chat_type: Option<String>
+}
+
+/// Выбранный пользователем результат inline запроса, приходит только если
+/// в BotFather включен inline feedback
+/// https://core.telegram.org/bots/api#choseninlineresult
+#[derive(Deserialize, Debug)]
+pub struct TelegramChosenInlineResultData{
+    pub result_id: String,
+    pub from: TelegramUserData,
+    pub query: String
+}
+
+/// Результат inline запроса в виде статьи
+/// https://core.telegram.org/bots/api#inlinequeryresultarticle
+#[derive(Serialize, Debug, Clone)]
+pub struct TelegramInlineQueryResultArticle{
+    /// Всегда `article`
+    #[serde(rename = "type")]
+    pub result_type: &'static str,
+    /// Уникальный в пределах ответа, не длиннее 64 байт
+    pub id: String,
+    pub title: String,
+    pub input_message_content: TelegramInputTextMessageContent,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>
+}
+
+impl TelegramInlineQueryResultArticle {
+    pub fn new(id: String, title: String, input_message_content: TelegramInputTextMessageContent) -> TelegramInlineQueryResultArticle{
+        TelegramInlineQueryResultArticle{
+            result_type: "article",
+            id,
+            title,
+            input_message_content,
+            url: None,
+            description: None
+        }
+    }
+}
+
+/// Текст сообщения, которое будет отправлено при выборе результата
+/// https://core.telegram.org/bots/api#inputtextmessagecontent
+#[derive(Serialize, Debug, Clone)]
+pub struct TelegramInputTextMessageContent{
+    pub message_text: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<TelegramParseMode>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_preview_options: Option<TelegramLinkPreviewOptions>
+}
+
+impl From<TelegramOutgoingText> for TelegramInputTextMessageContent {
+    fn from(text: TelegramOutgoingText) -> Self {
+        TelegramInputTextMessageContent{
+            message_text: text.text,
+            parse_mode: text.parse_mode,
+            link_preview_options: text.link_preview_options
+        }
+    }
+}
+
+/// Кнопка над результатами inline запроса, например для перехода в личный чат с ботом
+/// https://core.telegram.org/bots/api#inlinequeryresultsbutton
+#[derive(Serialize, Debug, Clone)]
+pub struct TelegramInlineQueryResultsButton{
+    pub text: String,
+    /// Параметр для `/start`, только `A-Za-z0-9_-`
+    pub start_parameter: String
+}
+
+/// Изменение статуса самого бота в чате: добавили, заблокировали и тд
+/// https://core.telegram.org/bots/api#chatmemberupdated
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)] // Полная структура из API, обработчики читают только часть полей
+pub struct TelegramChatMemberUpdatedData{
+    pub chat: TelegramChatData,
+    pub from: TelegramUserData,
+    pub date: i64,
+    pub old_chat_member: TelegramChatMemberData,
+    pub new_chat_member: TelegramChatMemberData
+}
+
+/// https://core.telegram.org/bots/api#chatmember
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)] // Бот пока смотрит только на статус
+pub struct TelegramChatMemberData{
+    /// creator, administrator, member, restricted, left или kicked
+    pub status: String,
+    pub user: TelegramUserData,
+
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>
+}
+
+impl TelegramChatMemberData {
+    /// Создатель или администратор чата
+    pub fn is_admin(&self) -> bool {
+        matches!(self.status.as_str(), "creator" | "administrator")
+    }
+}
+
+/// Изменение реакций на сообщение
+/// https://core.telegram.org/bots/api#messagereactionupdated
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)] // Полная структура из API, обработчики читают только часть полей
+pub struct TelegramMessageReactionUpdatedData{
+    pub chat: TelegramChatData,
+    pub message_id: TelegramMessageId,
+    pub user: Option<TelegramUserData>,
+    pub date: i64,
+    pub old_reaction: Vec<TelegramReactionTypeData>,
+    pub new_reaction: Vec<TelegramReactionTypeData>
+}
+
+/// https://core.telegram.org/bots/api#reactiontype
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TelegramReactionTypeData{
+    Emoji{
+        emoji: String
+    },
+    CustomEmoji{
+        custom_emoji_id: String
+    },
+    #[serde(other)]
+    Unknown
+}
+
+impl TelegramReactionTypeData {
+    /// Боты могут ставить только эмодзи из списка разрешенных Telegram
+    pub fn emoji(emoji: impl Into<String>) -> TelegramReactionTypeData{
+        TelegramReactionTypeData::Emoji{
+            emoji: emoji.into()
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,118 @@
+use std::{
+    collections::{
+        HashMap,
+        VecDeque
+    },
+    sync::{
+        Mutex
+    }
+};
+use futures::{
+    future::{
+        self,
+        BoxFuture,
+        FutureExt
+    }
+};
+use serde_json::{
+    json,
+    Value
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+use super::{
+    TelegramTransport
+};
+
+/// Транспорт без сети: ответы заранее складываются в очередь по имени метода,
+/// а все вызовы запоминаются для проверки
+#[derive(Debug, Default)]
+pub struct TelegramFakeTransport{
+    responses: Mutex<HashMap<String, VecDeque<Value>>>,
+    files: Mutex<HashMap<String, Vec<u8>>>,
+    calls: Mutex<Vec<(String, Value)>>
+}
+
+impl TelegramFakeTransport {
+    /// Тело ответа целиком, например с ошибкой
+    pub fn push_response(&self, method: &str, response: Value){
+        self.responses
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .entry(method.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Успешный ответ с указанным `result`
+    pub fn push_result(&self, method: &str, result: Value){
+        self.push_response(method, json!({
+            "ok": true,
+            "result": result
+        }));
+    }
+
+    /// Ошибка Bot API с кодом и описанием
+    pub fn push_error(&self, method: &str, error_code: i32, description: &str){
+        self.push_response(method, json!({
+            "ok": false,
+            "error_code": error_code,
+            "description": description
+        }));
+    }
+
+    pub fn put_file(&self, file_path: &str, data: Vec<u8>){
+        self.files
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .insert(file_path.to_string(), data);
+    }
+
+    /// Все вызовы по порядку: имя метода и параметры
+    pub fn calls(&self) -> Vec<(String, Value)>{
+        self.calls
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .clone()
+    }
+
+    fn next_response(&self, method: &str, params: &Value) -> Result<Value, TelegramBotError>{
+        self.calls
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .push((method.to_string(), params.clone()));
+
+        self.responses
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .get_mut(method)
+            .and_then(|queue| queue.pop_front())
+            .ok_or_else(|| TelegramBotError::TransportError(format!("No fake response for method {}", method)))
+    }
+
+    fn file(&self, file_path: &str, max_size: u64) -> Result<Vec<u8>, TelegramBotError>{
+        let data = self.files
+            .lock()
+            .expect("Fake transport lock poisoned")
+            .get(file_path)
+            .cloned()
+            .ok_or_else(|| TelegramBotError::TransportError(format!("No fake file {}", file_path)))?;
+        if data.len() as u64 > max_size {
+            return Err(TelegramBotError::FileTooLarge(max_size));
+        }
+        Ok(data)
+    }
+}
+
+impl TelegramTransport for TelegramFakeTransport {
+    fn call<'a>(&'a self, method: &'a str, params: &'a Value) -> BoxFuture<'a, Result<Value, TelegramBotError>>{
+        future::ready(self.next_response(method, params)).boxed()
+    }
+
+    fn download<'a>(&'a self, file_path: &'a str, max_size: u64) -> BoxFuture<'a, Result<Vec<u8>, TelegramBotError>>{
+        future::ready(self.file(file_path, max_size)).boxed()
+    }
+}
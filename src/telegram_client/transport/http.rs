@@ -0,0 +1,119 @@
+use std::{
+    path::{
+        Path
+    }
+};
+use futures::{
+    future::{
+        BoxFuture,
+        FutureExt
+    }
+};
+use reqwest::{
+    Client
+};
+use url::{
+    Url
+};
+use serde_json::{
+    Value
+};
+use tracing::{
+    debug,
+    trace
+};
+use reqwest_inspect_json::{
+    InspectJson
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+use super::{
+    TelegramTransport
+};
+
+/// Настоящие запросы к Bot API через reqwest
+#[derive(Debug)]
+pub struct TelegramHttpTransport{
+    http_client: Client,
+    api_url: Url,
+    /// Адрес для скачивания файлов вида `https://api.telegram.org/file/bot<token>/`
+    file_url: Url
+}
+
+impl TelegramHttpTransport {
+    pub fn new(http_client: Client, api_url: Url, file_url: Url) -> TelegramHttpTransport{
+        TelegramHttpTransport{
+            http_client,
+            api_url,
+            file_url
+        }
+    }
+
+    async fn execute_call(&self, method: &str, params: &Value) -> Result<Value, TelegramBotError>{
+        let url = self.api_url.join(method)?;
+        trace!("Method url: {}", url);
+
+        let response = self
+            .http_client
+            .post(url)
+            .json(params)
+            .send()
+            .await?
+            .inspect_json::<Value, TelegramBotError>(|d| { debug!("Method response: {}", d) })
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Скачивание по частям с проверкой размера заранее, если он известен, и по мере получения.
+    /// Локальный `telegram-bot-api` сервер отдает абсолютный путь к файлу на своем диске,
+    /// в таком случае читаем файл напрямую.
+    async fn execute_download(&self, file_path: &str, max_size: u64) -> Result<Vec<u8>, TelegramBotError>{
+        if Path::new(file_path).is_absolute() {
+            let metadata = tokio::fs::metadata(file_path).await?;
+            if metadata.len() > max_size {
+                return Err(TelegramBotError::FileTooLarge(max_size));
+            }
+            let data = tokio::fs::read(file_path).await?;
+            debug!("Local file read: {} bytes", data.len());
+            return Ok(data);
+        }
+
+        let url = self.file_url.join(file_path)?;
+
+        let mut response = self
+            .http_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        if response.content_length().filter(|length| *length > max_size).is_some() {
+            return Err(TelegramBotError::FileTooLarge(max_size));
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if (data.len() + chunk.len()) as u64 > max_size {
+                return Err(TelegramBotError::FileTooLarge(max_size));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        debug!("File downloaded: {} bytes", data.len());
+
+        Ok(data)
+    }
+}
+
+impl TelegramTransport for TelegramHttpTransport {
+    fn call<'a>(&'a self, method: &'a str, params: &'a Value) -> BoxFuture<'a, Result<Value, TelegramBotError>>{
+        self.execute_call(method, params).boxed()
+    }
+
+    fn download<'a>(&'a self, file_path: &'a str, max_size: u64) -> BoxFuture<'a, Result<Vec<u8>, TelegramBotError>>{
+        self.execute_download(file_path, max_size).boxed()
+    }
+}
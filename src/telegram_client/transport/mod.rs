@@ -0,0 +1,53 @@
+mod http;
+#[cfg(test)]
+mod fake;
+#[cfg(any(test, feature = "telegram_fixtures"))]
+mod record_replay;
+
+use std::{
+    fmt::{
+        Debug
+    }
+};
+use futures::{
+    future::{
+        BoxFuture
+    }
+};
+use serde_json::{
+    Value
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+
+pub use self::{
+    http::{
+        TelegramHttpTransport
+    }
+};
+#[cfg(any(test, feature = "telegram_fixtures"))]
+pub use self::{
+    record_replay::{
+        TelegramRecordReplayTransport
+    }
+};
+#[cfg(test)]
+pub use self::{
+    fake::{
+        TelegramFakeTransport
+    }
+};
+
+/// Способ доставки запросов до Bot API.
+/// Клиент работает только с JSON, поэтому транспорт можно подменить на фейковый или
+/// на запись и воспроизведение ответов из файла.
+pub trait TelegramTransport: Debug + Send + Sync {
+    /// Вызов метода, возвращает тело ответа как есть, в том числе и с ошибкой
+    fn call<'a>(&'a self, method: &'a str, params: &'a Value) -> BoxFuture<'a, Result<Value, TelegramBotError>>;
+
+    /// Содержимое файла по `file_path` из getFile, не больше `max_size` байт
+    fn download<'a>(&'a self, file_path: &'a str, max_size: u64) -> BoxFuture<'a, Result<Vec<u8>, TelegramBotError>>;
+}
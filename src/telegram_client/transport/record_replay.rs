@@ -0,0 +1,311 @@
+use std::{
+    path::{
+        PathBuf
+    },
+    sync::{
+        Arc,
+        Mutex
+    }
+};
+use tokio::{
+    fs::{
+        File
+    },
+    io::{
+        AsyncWriteExt
+    },
+    sync::{
+        Mutex as AsyncMutex
+    }
+};
+use futures::{
+    future::{
+        BoxFuture,
+        FutureExt
+    }
+};
+use serde::{
+    Serialize,
+    Deserialize
+};
+use serde_json::{
+    Value
+};
+use tracing::{
+    debug,
+    warn
+};
+use crate::{
+    error::{
+        TelegramBotError
+    }
+};
+use super::{
+    TelegramTransport
+};
+
+/// Одна пара запрос-ответ в файле с записью
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum TelegramExchange{
+    Call{
+        method: String,
+        params: Value,
+        response: Value
+    },
+    Download{
+        file_path: String,
+        data: Vec<u8>
+    }
+}
+
+#[derive(Debug)]
+enum Mode{
+    /// Запросы уходят в настоящий транспорт, ответы дописываются в файл
+    Record{
+        inner: Arc<dyn TelegramTransport>,
+        file: AsyncMutex<File>
+    },
+    /// Ответы берутся из файла, сеть не используется
+    Replay{
+        /// Обмены, которые еще не были использованы
+        exchanges: Mutex<Vec<Option<TelegramExchange>>>,
+        /// Сравнивать ли параметры запроса с записанными
+        check_params: bool
+    }
+}
+
+/// Запись и воспроизведение обмена с Bot API в файле JSON Lines, по одному обмену в строке.
+/// При воспроизведении каждому запросу отдается первый неиспользованный ответ того же метода
+/// с такими же параметрами, поэтому параллельные запросы не зависят от порядка.
+#[derive(Debug)]
+pub struct TelegramRecordReplayTransport{
+    mode: Mode,
+    fixture_path: PathBuf
+}
+
+impl TelegramRecordReplayTransport {
+    /// Файл создается заново, старая запись перезаписывается
+    pub fn record(inner: Arc<dyn TelegramTransport>, fixture_path: impl Into<PathBuf>) -> Result<TelegramRecordReplayTransport, TelegramBotError>{
+        let fixture_path = fixture_path.into();
+        let file = std::fs::File::create(&fixture_path)?;
+
+        Ok(TelegramRecordReplayTransport{
+            mode: Mode::Record{
+                inner,
+                file: AsyncMutex::new(File::from_std(file))
+            },
+            fixture_path
+        })
+    }
+
+    /// Воспроизведение со строгой проверкой параметров
+    pub fn replay(fixture_path: impl Into<PathBuf>) -> Result<TelegramRecordReplayTransport, TelegramBotError>{
+        let fixture_path = fixture_path.into();
+        let data = std::fs::read_to_string(&fixture_path)?;
+        let exchanges = data
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str::<TelegramExchange>(line).map(Some))
+            .collect::<Result<Vec<_>, _>>()?;
+        debug!("Fixture loaded: {} exchanges", exchanges.len());
+
+        Ok(TelegramRecordReplayTransport{
+            mode: Mode::Replay{
+                exchanges: Mutex::new(exchanges),
+                check_params: true
+            },
+            fixture_path
+        })
+    }
+
+    /// Ответы подбираются только по имени метода, отличия в параметрах логируются.
+    /// Нужно, если в параметрах есть что-то меняющееся от запуска к запуску.
+    pub fn ignore_params(mut self) -> TelegramRecordReplayTransport{
+        if let Mode::Replay{check_params, ..} = &mut self.mode {
+            *check_params = false;
+        }
+        self
+    }
+
+    /// Дописываем обмен одной строкой, блокировка держится до конца записи,
+    /// так параллельные запросы не перемешивают и не теряют строки
+    async fn save(file: &AsyncMutex<File>, exchange: &TelegramExchange) -> Result<(), TelegramBotError>{
+        let mut line = serde_json::to_vec(exchange)?;
+        line.push(b'\n');
+
+        let mut file = file.lock().await;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Забираем первый неиспользованный обмен, подходящий под условие
+    fn take_exchange<F>(&self, description: &str, is_match: F) -> Result<TelegramExchange, TelegramBotError>
+    where
+        F: Fn(&TelegramExchange) -> bool
+    {
+        let exchanges = match &self.mode {
+            Mode::Replay{exchanges, ..} => exchanges,
+            Mode::Record{..} => unreachable!("Exchanges are taken only in replay mode")
+        };
+        exchanges
+            .lock()
+            .expect("Replay transport lock poisoned")
+            .iter_mut()
+            .find(|exchange| matches!(exchange, Some(exchange) if is_match(exchange)))
+            .and_then(Option::take)
+            .ok_or_else(|| TelegramBotError::TransportError(format!("Fixture {:?} has no recorded {}", self.fixture_path, description)))
+    }
+
+    async fn execute_call(&self, method: &str, params: &Value) -> Result<Value, TelegramBotError>{
+        match &self.mode {
+            Mode::Record{inner, file} => {
+                let response = inner.call(method, params).await?;
+                Self::save(file, &TelegramExchange::Call{
+                    method: method.to_string(),
+                    params: params.clone(),
+                    response: response.clone()
+                }).await?;
+                Ok(response)
+            },
+            Mode::Replay{check_params, ..} => {
+                let check_params = *check_params;
+                let description = format!("call {} with params {}", method, params);
+                let exchange = self.take_exchange(&description, |exchange|{
+                    match exchange {
+                        TelegramExchange::Call{method: recorded_method, params: recorded_params, ..} => {
+                            recorded_method == method && (!check_params || recorded_params == params)
+                        },
+                        TelegramExchange::Download{..} => false
+                    }
+                })?;
+                match exchange {
+                    TelegramExchange::Call{params: recorded_params, response, ..} => {
+                        if &recorded_params != params {
+                            warn!("Replay params differ for method {}: recorded {}, actual {}", method, recorded_params, params);
+                        }
+                        Ok(response)
+                    },
+                    TelegramExchange::Download{..} => unreachable!("Only calls match a call")
+                }
+            }
+        }
+    }
+
+    async fn execute_download(&self, file_path: &str, max_size: u64) -> Result<Vec<u8>, TelegramBotError>{
+        match &self.mode {
+            Mode::Record{inner, file} => {
+                let data = inner.download(file_path, max_size).await?;
+                Self::save(file, &TelegramExchange::Download{
+                    file_path: file_path.to_string(),
+                    data: data.clone()
+                }).await?;
+                Ok(data)
+            },
+            Mode::Replay{..} => {
+                let description = format!("download {}", file_path);
+                let exchange = self.take_exchange(&description, |exchange|{
+                    matches!(exchange, TelegramExchange::Download{file_path: recorded_path, ..} if recorded_path == file_path)
+                })?;
+                match exchange {
+                    TelegramExchange::Download{data, ..} => {
+                        if data.len() as u64 > max_size {
+                            return Err(TelegramBotError::FileTooLarge(max_size));
+                        }
+                        Ok(data)
+                    },
+                    TelegramExchange::Call{..} => unreachable!("Only downloads match a download")
+                }
+            }
+        }
+    }
+}
+
+impl TelegramTransport for TelegramRecordReplayTransport {
+    fn call<'a>(&'a self, method: &'a str, params: &'a Value) -> BoxFuture<'a, Result<Value, TelegramBotError>>{
+        self.execute_call(method, params).boxed()
+    }
+
+    fn download<'a>(&'a self, file_path: &'a str, max_size: u64) -> BoxFuture<'a, Result<Vec<u8>, TelegramBotError>>{
+        self.execute_download(file_path, max_size).boxed()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json
+    };
+    use super::{
+        super::{
+            TelegramFakeTransport
+        },
+        *
+    };
+
+    /// Отдельный файл для каждого теста, тесты идут параллельно
+    fn fixture_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pocket_telegram_bot_{}_{}.jsonl", std::process::id(), name))
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let path = fixture_path("record_then_replay");
+
+        let fake = Arc::new(TelegramFakeTransport::default());
+        for index in 0..10 {
+            fake.push_result("sendMessage", json!({"message_id": index}));
+        }
+        fake.put_file("documents/file_1.txt", b"example.com".to_vec());
+        let record = Arc::new(TelegramRecordReplayTransport::record(fake, &path).unwrap());
+
+        // Параллельные запросы не должны терять записи
+        let calls = (0..10).map(|_|{
+            let record = record.clone();
+            tokio::spawn(async move {
+                record.call("sendMessage", &json!({"chat_id": 1})).await.unwrap()
+            })
+        });
+        futures::future::join_all(calls).await;
+        record.download("documents/file_1.txt", 1024).await.unwrap();
+
+        let replay = TelegramRecordReplayTransport::replay(&path).unwrap();
+        let mut message_ids = Vec::new();
+        for _ in 0..10 {
+            let response = replay.call("sendMessage", &json!({"chat_id": 1})).await.unwrap();
+            message_ids.push(response["result"]["message_id"].as_i64().unwrap());
+        }
+        message_ids.sort_unstable();
+        assert_eq!(message_ids, (0..10).collect::<Vec<i64>>());
+        assert_eq!(replay.download("documents/file_1.txt", 1024).await.unwrap(), b"example.com");
+        assert!(replay.call("sendMessage", &json!({"chat_id": 1})).await.is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_matches_params() {
+        let path = fixture_path("replay_matches_params");
+        std::fs::write(&path, concat!(
+            r#"{"kind":"call","method":"sendMessage","params":{"chat_id":1},"response":{"ok":true,"result":1}}"#, "\n",
+            r#"{"kind":"call","method":"sendMessage","params":{"chat_id":2},"response":{"ok":true,"result":2}}"#, "\n"
+        )).unwrap();
+
+        // Порядок не важен, важны параметры
+        let replay = TelegramRecordReplayTransport::replay(&path).unwrap();
+        assert!(matches!(replay.call("sendMessage", &json!({"chat_id": 3})).await, Err(TelegramBotError::TransportError(_))));
+        assert_eq!(replay.call("sendMessage", &json!({"chat_id": 2})).await.unwrap()["result"], json!(2));
+        assert_eq!(replay.call("sendMessage", &json!({"chat_id": 1})).await.unwrap()["result"], json!(1));
+        assert!(replay.call("getMe", &json!({})).await.is_err());
+
+        // Без проверки параметров ответы отдаются по порядку записи
+        let replay = TelegramRecordReplayTransport::replay(&path).unwrap().ignore_params();
+        assert_eq!(replay.call("sendMessage", &json!({"chat_id": 3})).await.unwrap()["result"], json!(1));
+        assert_eq!(replay.call("sendMessage", &json!({"chat_id": 3})).await.unwrap()["result"], json!(2));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    collections::{
+        HashMap
+    }
+};
+use serde::{
+    de::{
+        Error,
+        DeserializeOwned
+    },
+    Deserialize,
+    Deserializer
+};
+use serde_json::{
+    Value
+};
+use tracing::{
+    warn
+};
+use super::{
+    responses::{
+        TelegramMessageData,
+        TelegramCallbackQueryData,
+        TelegramInlineQueryData,
+        TelegramChosenInlineResultData,
+        TelegramChatMemberUpdatedData,
+        TelegramMessageReactionUpdatedData
+    }
+};
+
+////////////////////////////////////////////////////////////////////////
+
+/// Одно обновление из getUpdates или webhook
+/// https://core.telegram.org/bots/api#update
+#[derive(Debug)]
+pub struct TelegramUpdateData{
+    pub update_id: i64,
+    pub kind: TelegramUpdateKind
+}
+
+/// Содержимое обновления, в каждом обновлении присутствует ровно одно поле
+#[derive(Debug)]
+pub enum TelegramUpdateKind{
+    Message(TelegramMessageData),
+    EditedMessage(TelegramMessageData),
+    ChannelPost(TelegramMessageData),
+    EditedChannelPost(TelegramMessageData),
+    CallbackQuery(TelegramCallbackQueryData),
+    InlineQuery(TelegramInlineQueryData),
+    ChosenInlineResult(TelegramChosenInlineResultData),
+    MyChatMember(TelegramChatMemberUpdatedData),
+    MessageReaction(TelegramMessageReactionUpdatedData),
+
+    /// Обновления, которые мы пока не разбираем, храним как есть
+    Unknown(HashMap<String, Value>)
+}
+
+impl TelegramUpdateKind {
+    /// Имя поля обновления, оно же используется в `allowed_updates`
+    pub fn name(&self) -> &str {
+        match self {
+            TelegramUpdateKind::Message(_) => "message",
+            TelegramUpdateKind::EditedMessage(_) => "edited_message",
+            TelegramUpdateKind::ChannelPost(_) => "channel_post",
+            TelegramUpdateKind::EditedChannelPost(_) => "edited_channel_post",
+            TelegramUpdateKind::CallbackQuery(_) => "callback_query",
+            TelegramUpdateKind::InlineQuery(_) => "inline_query",
+            TelegramUpdateKind::ChosenInlineResult(_) => "chosen_inline_result",
+            TelegramUpdateKind::MyChatMember(_) => "my_chat_member",
+            TelegramUpdateKind::MessageReaction(_) => "message_reaction",
+            TelegramUpdateKind::Unknown(raw) => {
+                raw.keys()
+                    .next()
+                    .map(|k| k.as_str())
+                    .unwrap_or("unknown")
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+/// Достаем из сырого обновления поле с указанным именем и парсим его.
+/// Если разобрать не вышло, то поле остается на месте и обновление попадет в `Unknown`,
+/// иначе одно кривое обновление блокировало бы получение всех остальных.
+fn take_field<T>(raw: &mut HashMap<String, Value>, name: &str) -> Option<T>
+where
+    T: DeserializeOwned
+{
+    let value = raw.get(name)?;
+    match T::deserialize(value) {
+        Ok(data) => {
+            raw.remove(name);
+            Some(data)
+        },
+        Err(err) => {
+            warn!("Invalid {} update: {}", name, err);
+            None
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TelegramUpdateData {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let mut raw = HashMap::<String, Value>::deserialize(deserializer)?;
+
+        let update_id = raw
+            .remove("update_id")
+            .ok_or_else(|| D::Error::missing_field("update_id"))?
+            .as_i64()
+            .ok_or_else(|| D::Error::custom("update_id is not an integer"))?;
+
+        let kind = if let Some(data) = take_field(&mut raw, "message") {
+            TelegramUpdateKind::Message(data)
+        } else if let Some(data) = take_field(&mut raw, "edited_message") {
+            TelegramUpdateKind::EditedMessage(data)
+        } else if let Some(data) = take_field(&mut raw, "channel_post") {
+            TelegramUpdateKind::ChannelPost(data)
+        } else if let Some(data) = take_field(&mut raw, "edited_channel_post") {
+            TelegramUpdateKind::EditedChannelPost(data)
+        } else if let Some(data) = take_field(&mut raw, "callback_query") {
+            TelegramUpdateKind::CallbackQuery(data)
+        } else if let Some(data) = take_field(&mut raw, "inline_query") {
+            TelegramUpdateKind::InlineQuery(data)
+        } else if let Some(data) = take_field(&mut raw, "chosen_inline_result") {
+            TelegramUpdateKind::ChosenInlineResult(data)
+        } else if let Some(data) = take_field(&mut raw, "my_chat_member") {
+            TelegramUpdateKind::MyChatMember(data)
+        } else if let Some(data) = take_field(&mut raw, "message_reaction") {
+            TelegramUpdateKind::MessageReaction(data)
+        } else {
+            TelegramUpdateKind::Unknown(raw)
+        };
+
+        Ok(TelegramUpdateData{
+            update_id,
+            kind
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json
+    };
+    use super::{
+        super::{
+            responses::{
+                TelegramReactionTypeData
+            }
+        },
+        *
+    };
+
+    #[test]
+    fn test_known_update(){
+        let update: TelegramUpdateData = serde_json::from_value(json!({
+            "update_id": 10,
+            "message_reaction": {
+                "chat": {"id": -100123, "type": "supergroup", "title": "Links"},
+                "message_id": 42,
+                "user": {"id": 7, "is_bot": false, "first_name": "User", "username": "user"},
+                "date": 1700000000,
+                "old_reaction": [],
+                "new_reaction": [
+                    {"type": "emoji", "emoji": "👍"},
+                    {"type": "custom_emoji", "custom_emoji_id": "12345"},
+                    {"type": "paid"}
+                ]
+            }
+        })).unwrap();
+
+        assert_eq!(update.update_id, 10);
+        assert_eq!(update.kind.name(), "message_reaction");
+        let reaction = match update.kind {
+            TelegramUpdateKind::MessageReaction(reaction) => reaction,
+            other => panic!("Unexpected update: {:?}", other)
+        };
+        assert_eq!(reaction.chat.id, -100123);
+        assert_eq!(reaction.message_id, 42);
+        assert_eq!(reaction.user.and_then(|user| user.username).as_deref(), Some("user"));
+        assert!(reaction.old_reaction.is_empty());
+        assert!(matches!(&reaction.new_reaction[0], TelegramReactionTypeData::Emoji{emoji} if emoji == "👍"));
+        assert!(matches!(&reaction.new_reaction[1], TelegramReactionTypeData::CustomEmoji{custom_emoji_id} if custom_emoji_id == "12345"));
+        assert!(matches!(&reaction.new_reaction[2], TelegramReactionTypeData::Unknown));
+    }
+
+    #[test]
+    fn test_unknown_update_kind(){
+        let update: TelegramUpdateData = serde_json::from_value(json!({
+            "update_id": 11,
+            "poll": {"id": "1", "question": "?"}
+        })).unwrap();
+
+        assert_eq!(update.update_id, 11);
+        assert_eq!(update.kind.name(), "poll");
+        assert!(matches!(update.kind, TelegramUpdateKind::Unknown(ref raw) if raw.contains_key("poll")));
+    }
+
+    #[test]
+    fn test_invalid_known_update_is_unknown(){
+        let update: TelegramUpdateData = serde_json::from_value(json!({
+            "update_id": 12,
+            "my_chat_member": {
+                "chat": {"id": 1, "type": "private"},
+                "date": "not a number"
+            }
+        })).unwrap();
+
+        assert_eq!(update.update_id, 12);
+        assert_eq!(update.kind.name(), "my_chat_member");
+        assert!(matches!(update.kind, TelegramUpdateKind::Unknown(ref raw) if raw.contains_key("my_chat_member")));
+    }
+
+    #[test]
+    fn test_missing_update_id(){
+        let result = serde_json::from_value::<TelegramUpdateData>(json!({
+            "message": {}
+        }));
+
+        assert!(result.is_err());
+    }
+}
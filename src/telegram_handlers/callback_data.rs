@@ -0,0 +1,47 @@
+use super::{
+    item_actions::{
+        ItemAction
+    }
+};
+
+/// Действие inline кнопки, передается в `callback_data`.
+/// Telegram ограничивает данные 64 байтами, поэтому формат короткий: `действие:аргумент`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallbackAction {
+    /// Страница списка непрочитанного, начиная с нуля
+    ListPage(usize),
+    /// Страница результатов поиска, сам запрос хранится для сообщения в базе
+    SearchPage(usize),
+    /// Действие с элементом по его `item_id`
+    Item(ItemAction, String)
+}
+
+impl CallbackAction {
+    pub fn to_data(&self) -> String {
+        match self {
+            CallbackAction::ListPage(page) => format!("list:{}", page),
+            CallbackAction::SearchPage(page) => format!("search:{}", page),
+            CallbackAction::Item(action, item_id) => format!("{}:{}", action.code(), item_id)
+        }
+    }
+
+    pub fn parse(data: &str) -> Option<CallbackAction> {
+        let mut parts = data.splitn(2, ':');
+        let action = parts.next()?;
+        let argument = parts
+            .next()
+            .filter(|argument| !argument.is_empty())?;
+        match action {
+            "list" => argument
+                .parse()
+                .ok()
+                .map(CallbackAction::ListPage),
+            "search" => argument
+                .parse()
+                .ok()
+                .map(CallbackAction::SearchPage),
+            code => ItemAction::from_code(code)
+                .map(|action| CallbackAction::Item(action, argument.to_string()))
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use tracing::{
+    instrument,
+    debug,
+    error
+};
+use tap::{
+    prelude::{
+        *
+    }
+};
+use crate::{
+    app::{
+        Application
+    },
+    error::{
+        TelegramBotError
+    },
+    telegram_client::{
+        TelegramChatId,
+        TelegramUserId,
+        TelegramMessageId,
+        TelegramCallbackQueryData,
+        TelegramChatAction
+    },
+    model::{
+        UserState,
+        SearchSession
+    }
+};
+use super::{
+    callback_data::{
+        CallbackAction
+    },
+    item_actions::{
+        send_item_actions,
+        update_saved_item_message
+    },
+    item_list::{
+        ItemsSource,
+        edit_items_list
+    },
+    search_query::{
+        SearchQuery
+    },
+    router::{
+        can_manage_chat
+    }
+};
+
+/// Показ страницы поиска по сохраненному для сообщения запросу.
+/// Возвращает уведомление, если запрос уже не найти.
+#[instrument(skip(app, pocket_api_token))]
+async fn show_search_page(app: &Application,
+                          pocket_api_token: &str,
+                          chat_id: TelegramChatId,
+                          message_id: TelegramMessageId,
+                          page: Option<usize>) -> Result<Option<&'static str>, TelegramBotError> {
+    let session = app
+        .redis_client
+        .get_search_session(chat_id, message_id)
+        .await
+        .tap_err(|e|{ error!("Get search session error: {}", e) })?;
+    let session = match session {
+        Some(session) => session,
+        None => return Ok(Some("Search results are expired, use /search again"))
+    };
+    let query = match SearchQuery::parse(&session.args) {
+        Ok(query) => query,
+        Err(_) => return Ok(Some("Search results are expired, use /search again"))
+    };
+
+    // Без номера страницы обновляем текущую, например после действия с элементом
+    let page = page.unwrap_or(session.page);
+    edit_items_list(app, pocket_api_token, chat_id, message_id, &ItemsSource::Search(&query), page).await?;
+
+    app
+        .redis_client
+        .set_search_session(chat_id, message_id, &SearchSession{
+            args: session.args,
+            page
+        })
+        .await
+        .tap_err(|e|{ error!("Set search session error: {}", e) })?;
+
+    Ok(None)
+}
+
+#[instrument(skip(app, pocket_api_token))]
+async fn process_authorized_action(app: &Application,
+                                   pocket_api_token: &str,
+                                   chat_id: TelegramChatId,
+                                   message_id: TelegramMessageId,
+                                   is_private: bool,
+                                   user_id: TelegramUserId,
+                                   action: CallbackAction) -> Result<Option<&'static str>, TelegramBotError> {
+    match action {
+        CallbackAction::ListPage(page) => {
+            edit_items_list(app, pocket_api_token, chat_id, message_id, &ItemsSource::Unread, page).await?;
+            Ok(None)
+        },
+        CallbackAction::SearchPage(page) => {
+            show_search_page(app, pocket_api_token, chat_id, message_id, Some(page)).await
+        },
+        CallbackAction::Item(action, item_id) => {
+            // Кнопки под ссылками в группе видят все, но менять общий список могут только администраторы
+            if !can_manage_chat(app, chat_id, is_private, Some(user_id)).await? {
+                return Ok(Some("Only chat administrators can change saved items"));
+            }
+
+            // Обновление страницы поиска ниже показывает действие само, при загрузке элементов
+            let results = app
+                .telegram_client
+                .with_chat_action(chat_id, TelegramChatAction::Typing, send_item_actions(app, pocket_api_token, action, &[item_id]))
+                .await?;
+            if !results.first().copied().unwrap_or(false) {
+                return Ok(Some("Pocket rejected the action"));
+            }
+
+            // Кнопки под подтверждением или в результатах поиска должны отражать новое состояние элемента
+            update_saved_item_message(app, chat_id, message_id, action)
+                .await
+                .tap_err(|e|{ error!("Saved item message update error: {}", e) })
+                .ok();
+            show_search_page(app, pocket_api_token, chat_id, message_id, None)
+                .await
+                .tap_err(|e|{ error!("Search results refresh error: {}", e) })
+                .ok();
+
+            Ok(Some(action.done_text()))
+        }
+    }
+}
+
+/// Нажатие на inline кнопку в чате
+#[instrument(skip(app))]
+pub async fn process_callback_query(app: &Application, chat_id: TelegramChatId, callback_query: TelegramCallbackQueryData) -> Result<(), TelegramBotError> {
+    let action = callback_query
+        .data
+        .as_deref()
+        .and_then(CallbackAction::parse);
+    let message = callback_query
+        .message
+        .as_ref()
+        .map(|message| (message.message_id, message.chat.is_private()));
+    debug!("Callback action: {:?}", action);
+
+    let state = app
+        .redis_client
+        .get_user_state(chat_id)
+        .await
+        .tap_err(|e|{ error!("Get user state error: {}", e) })?;
+
+    let notification = match (action, message, state) {
+        (Some(action), Some((message_id, is_private)), UserState::Authorized{pocket_api_token}) => {
+            // Ошибка уже залогирована, пользователю достаточно уведомления
+            process_authorized_action(app, &pocket_api_token, chat_id, message_id, is_private, callback_query.from.id, action)
+                .await
+                .unwrap_or(Some("Action failed, try again later"))
+        },
+        (Some(_), _, UserState::Authorized{..}) => Some("Message is too old, use the command again"),
+        (Some(_), _, _) => Some("Connect your Pocket account with /start"),
+        (None, _, _) => Some("Action is not supported")
+    };
+
+    // Без ответа у пользователя будет висеть индикатор загрузки
+    app
+        .telegram_client
+        .answer_callback_query(callback_query.id, notification.map(|text| text.to_string()), false)
+        .await
+        .tap_err(|e|{ error!("Callback answer error: {}", e) })?;
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc
+        }
+    };
+    use serde_json::{
+        json,
+        Value
+    };
+    use crate::{
+        telegram_client::{
+            TelegramFakeTransport
+        },
+        pocket_items::{
+            PocketFakeTransport
+        },
+        model::{
+            SavedItem
+        }
+    };
+    use super::{
+        super::{
+            item_actions::{
+                ItemAction
+            }
+        },
+        *
+    };
+
+    const CHAT_ID: TelegramChatId = 1000;
+    const MESSAGE_ID: TelegramMessageId = 11;
+
+    async fn app_with_state(state: UserState) -> (Application, Arc<TelegramFakeTransport>, Arc<PocketFakeTransport>) {
+        let telegram = Arc::new(TelegramFakeTransport::default());
+        let pocket = Arc::new(PocketFakeTransport::default());
+        let app = Application::with_fakes(telegram.clone(), pocket.clone());
+        app.redis_client
+            .set_user_state(CHAT_ID, state, None)
+            .await
+            .unwrap();
+        (app, telegram, pocket)
+    }
+
+    fn authorized() -> UserState {
+        UserState::Authorized{
+            pocket_api_token: "token".to_string()
+        }
+    }
+
+    fn callback_query(data: &str) -> TelegramCallbackQueryData {
+        chat_callback_query(json!({"id": CHAT_ID, "type": "private"}), data)
+    }
+
+    fn chat_callback_query(chat: Value, data: &str) -> TelegramCallbackQueryData {
+        serde_json::from_value(json!({
+            "id": "query",
+            "from": {"id": CHAT_ID, "is_bot": false, "first_name": "User"},
+            "message": {
+                "message_id": MESSAGE_ID,
+                "chat": chat,
+                "text": "Saved"
+            },
+            "data": data
+        })).unwrap()
+    }
+
+    /// Вызовы Telegram без индикатора набора, он отправляется в зависимости от времени
+    fn telegram_calls(telegram: &TelegramFakeTransport) -> Vec<(String, Value)> {
+        telegram
+            .calls()
+            .into_iter()
+            .filter(|(method, _)| method != "sendChatAction")
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_archive_button_updates_saved_item() {
+        let (app, telegram, pocket) = app_with_state(authorized()).await;
+        app.redis_client
+            .set_saved_item(CHAT_ID, MESSAGE_ID, Some(&SavedItem{
+                item_id: "555".to_string(),
+                archived: false,
+                favorite: false
+            }))
+            .await
+            .unwrap();
+        pocket.push_result("send", json!({"action_results": [true], "status": 1}));
+        telegram.push_result("editMessageReplyMarkup", json!({
+            "message_id": MESSAGE_ID,
+            "chat": {"id": CHAT_ID, "type": "private"},
+            "text": "Saved"
+        }));
+        telegram.push_result("answerCallbackQuery", json!(true));
+
+        let data = CallbackAction::Item(ItemAction::Archive, "555".to_string()).to_data();
+        process_callback_query(&app, CHAT_ID, callback_query(&data)).await.unwrap();
+
+        let pocket_calls = pocket.calls();
+        assert_eq!(pocket_calls.len(), 1);
+        assert_eq!(pocket_calls[0].0, "send");
+        assert_eq!(pocket_calls[0].1["actions"][0]["action"], json!("archive"));
+        assert_eq!(pocket_calls[0].1["actions"][0]["item_id"], json!("555"));
+
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].0, "editMessageReplyMarkup");
+        assert_eq!(calls[0].1["chat_id"], json!(CHAT_ID));
+        assert_eq!(calls[0].1["message_id"], json!(MESSAGE_ID));
+        assert_eq!(calls[0].1["reply_markup"]["inline_keyboard"][0][0]["callback_data"], json!("readd:555"));
+        assert_eq!(calls[1].0, "answerCallbackQuery");
+        assert_eq!(calls[1].1["callback_query_id"], json!("query"));
+        assert_eq!(calls[1].1["text"], json!("Archived"));
+
+        let saved_item = app.redis_client.get_saved_item(CHAT_ID, MESSAGE_ID).await.unwrap().unwrap();
+        assert!(saved_item.archived);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_action_keeps_saved_item() {
+        let (app, telegram, pocket) = app_with_state(authorized()).await;
+        app.redis_client
+            .set_saved_item(CHAT_ID, MESSAGE_ID, Some(&SavedItem{
+                item_id: "555".to_string(),
+                archived: false,
+                favorite: false
+            }))
+            .await
+            .unwrap();
+        pocket.push_result("send", json!({"action_results": [false], "status": 0}));
+        telegram.push_result("answerCallbackQuery", json!(true));
+
+        let data = CallbackAction::Item(ItemAction::Favorite, "555".to_string()).to_data();
+        process_callback_query(&app, CHAT_ID, callback_query(&data)).await.unwrap();
+
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1["text"], json!("Pocket rejected the action"));
+
+        let saved_item = app.redis_client.get_saved_item(CHAT_ID, MESSAGE_ID).await.unwrap().unwrap();
+        assert!(!saved_item.favorite);
+    }
+
+    #[tokio::test]
+    async fn test_button_without_authorization() {
+        let (app, telegram, pocket) = app_with_state(UserState::Unauthorized).await;
+        telegram.push_result("answerCallbackQuery", json!(true));
+
+        let data = CallbackAction::Item(ItemAction::Delete, "555".to_string()).to_data();
+        process_callback_query(&app, CHAT_ID, callback_query(&data)).await.unwrap();
+
+        assert!(pocket.calls().is_empty());
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1["text"], json!("Connect your Pocket account with /start"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_button() {
+        let (app, telegram, pocket) = app_with_state(authorized()).await;
+        telegram.push_result("answerCallbackQuery", json!(true));
+
+        process_callback_query(&app, CHAT_ID, callback_query("unknown:1")).await.unwrap();
+
+        assert!(pocket.calls().is_empty());
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1["text"], json!("Action is not supported"));
+    }
+
+    #[tokio::test]
+    async fn test_group_item_button_requires_admin() {
+        let group_chat_id: TelegramChatId = -1001;
+        let (app, telegram, pocket) = app_with_state(authorized()).await;
+        app.redis_client
+            .set_user_state(group_chat_id, authorized(), None)
+            .await
+            .unwrap();
+        telegram.push_result("getChatMember", json!({
+            "status": "member",
+            "user": {"id": CHAT_ID, "is_bot": false, "first_name": "User"}
+        }));
+        telegram.push_result("answerCallbackQuery", json!(true));
+
+        let data = CallbackAction::Item(ItemAction::Delete, "555".to_string()).to_data();
+        let query = chat_callback_query(json!({"id": group_chat_id, "type": "group", "title": "Team"}), &data);
+        process_callback_query(&app, group_chat_id, query).await.unwrap();
+
+        assert!(pocket.calls().is_empty());
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].1, json!({"chat_id": group_chat_id, "user_id": CHAT_ID}));
+        assert_eq!(calls[1].1["text"], json!("Only chat administrators can change saved items"));
+    }
+}
@@ -0,0 +1,471 @@
+use tracing::{
+    instrument,
+    debug
+};
+use crate::{
+    error::{
+        TelegramBotError
+    },
+    telegram_client::{
+        TelegramClient,
+        TelegramBotCommand,
+        TelegramBotCommandScope,
+        TelegramFormattedText,
+        TelegramParseMode
+    },
+    model::{
+        UserStateKind
+    }
+};
+use super::{
+    search_query::{
+        SEARCH_USAGE
+    },
+    router::{
+        find_route
+    }
+};
+
+////////////////////////////////////////////////////////////////////////
+
+/// Все команды, которые понимает бот
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotCommand {
+    Start,
+    Stop,
+    Save,
+    Dashboard,
+    List,
+    Search,
+    Archive,
+    Readd,
+    Favorite,
+    Unfavorite,
+    Delete,
+    Help
+}
+
+/// Описание команды: по нему строится меню в Telegram, обработчики регистрируются в роутере
+#[derive(Debug)]
+pub struct BotCommandInfo {
+    pub command: BotCommand,
+    /// Имя без `/`
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Синтаксис аргументов для справки, пустой если аргументов нет
+    pub usage: &'static str,
+    /// Примеры использования целиком, вместе с командой
+    pub examples: &'static [&'static str],
+    /// Переводы описания: код языка и текст
+    pub localized_descriptions: &'static [(&'static str, &'static str)],
+    /// В каких чатах команда показывается в меню
+    pub scopes: &'static [TelegramBotCommandScope]
+}
+
+impl BotCommandInfo {
+    /// Команда доступна, если для состояния зарегистрирован обработчик
+    pub fn is_available_in(&self, state: UserStateKind) -> bool {
+        find_route(self.command, state).is_some()
+    }
+}
+
+pub const BOT_COMMANDS: &[BotCommandInfo] = &[
+    BotCommandInfo{
+        command: BotCommand::Start,
+        name: "start",
+        description: "Connect your Pocket account",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Подключить аккаунт Pocket")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Stop,
+        name: "stop",
+        description: "Disconnect your Pocket account",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Отключить аккаунт Pocket")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Save,
+        name: "save",
+        description: "Save links from the message you reply to",
+        usage: "[link ...]",
+        examples: &["/save", "/save https://example.com/article"],
+        localized_descriptions: &[
+            ("ru", "Сохранить ссылки из сообщения, на которое вы отвечаете")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllGroupChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Dashboard,
+        name: "dashboard",
+        description: "Pin a live status message",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Закрепить сообщение со статусом")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::List,
+        name: "list",
+        description: "Show unread items",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Показать непрочитанное")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Search,
+        name: "search",
+        description: "Search saved items",
+        usage: SEARCH_USAGE,
+        examples: &["/search rust async", "/search tag:work state:unread", "/search domain:medium.com favorite"],
+        localized_descriptions: &[
+            ("ru", "Найти сохраненное")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Archive,
+        name: "archive",
+        description: "Archive the last saved link or the one you reply to",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Архивировать сохраненную ссылку")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllGroupChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Readd,
+        name: "readd",
+        description: "Move the last saved link or the one you reply to back to unread",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Вернуть ссылку в непрочитанное")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllGroupChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Favorite,
+        name: "favorite",
+        description: "Add the last saved link or the one you reply to to favorites",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Добавить ссылку в избранное")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllGroupChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Unfavorite,
+        name: "unfavorite",
+        description: "Remove the last saved link or the one you reply to from favorites",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Убрать ссылку из избранного")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllGroupChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Delete,
+        name: "delete",
+        description: "Delete the last saved link or the one you reply to from Pocket",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Удалить ссылку из Pocket")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllGroupChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    },
+    BotCommandInfo{
+        command: BotCommand::Help,
+        name: "help",
+        description: "Show available commands",
+        usage: "",
+        examples: &[],
+        localized_descriptions: &[
+            ("ru", "Показать доступные команды")
+        ],
+        scopes: &[
+            TelegramBotCommandScope::AllPrivateChats,
+            TelegramBotCommandScope::AllGroupChats,
+            TelegramBotCommandScope::AllChatAdministrators
+        ]
+    }
+];
+
+/// Области, для которых регистрируем меню
+const REGISTERED_SCOPES: &[TelegramBotCommandScope] = &[
+    TelegramBotCommandScope::AllPrivateChats,
+    TelegramBotCommandScope::AllGroupChats,
+    TelegramBotCommandScope::AllChatAdministrators
+];
+
+////////////////////////////////////////////////////////////////////////
+
+/// Команда из текста сообщения вида `/name@botname аргументы`
+#[derive(Debug)]
+pub struct ParsedCommand<'a> {
+    /// Имя без `/`
+    pub name: &'a str,
+    /// Имя бота без `@`, если команда адресована конкретному боту
+    pub mention: Option<&'a str>,
+    pub args: &'a str
+}
+
+/// Разбор команды, если текст с нее начинается
+pub fn parse_command(text: &str) -> Option<ParsedCommand<'_>> {
+    let text = text.trim_start();
+    let rest = text.strip_prefix('/')?;
+    let (command, args) = match rest.find(char::is_whitespace) {
+        Some(index) => (&rest[..index], rest[index..].trim()),
+        None => (rest, "")
+    };
+
+    let mut command_parts = command.splitn(2, '@');
+    let name = command_parts
+        .next()
+        .filter(|name| !name.is_empty())?;
+    let mention = command_parts
+        .next()
+        .filter(|mention| !mention.is_empty());
+
+    Some(ParsedCommand{
+        name,
+        mention,
+        args
+    })
+}
+
+/// Описание команды по имени без `/`
+pub fn find_command_info(name: &str) -> Option<&'static BotCommandInfo> {
+    BOT_COMMANDS
+        .iter()
+        .find(|info| info.name.eq_ignore_ascii_case(name))
+}
+
+/// Справка по командам, которые доступны в текущем состоянии
+pub fn help_text(state: UserStateKind) -> TelegramFormattedText {
+    let hint = match state {
+        UserStateKind::Unauthorized => "Connect your Pocket account with /start to save links.",
+        UserStateKind::AutorizationConfirmationWaiting => "Open the auth link above to finish connecting your Pocket account.",
+        UserStateKind::Authorized => "Send a link, forward a post or a .txt/.html file to save it to Pocket."
+    };
+
+    let mut text = TelegramFormattedText::new(TelegramParseMode::MarkdownV2)
+        .text(hint)
+        .new_line()
+        .new_line()
+        .bold("Commands:");
+    for info in BOT_COMMANDS.iter().filter(|info| info.is_available_in(state)) {
+        let command = if info.usage.is_empty() {
+            format!("/{}", info.name)
+        } else {
+            format!("/{} {}", info.name, info.usage)
+        };
+        text = text
+            .new_line()
+            .code(command)
+            .text(" - ")
+            .text(info.description);
+        for example in info.examples {
+            text = text
+                .new_line()
+                .text("    e.g. ")
+                .code(*example);
+        }
+    }
+
+    text
+}
+
+/// Отправляем таблицу команд в Telegram, чтобы они были видны в меню
+#[instrument(skip(client))]
+pub async fn register_bot_commands(client: &TelegramClient) -> Result<(), TelegramBotError> {
+    for scope in REGISTERED_SCOPES.iter().copied() {
+        let scope_commands: Vec<&BotCommandInfo> = BOT_COMMANDS
+            .iter()
+            .filter(|info| info.scopes.contains(&scope))
+            .collect();
+
+        // Список по-умолчанию
+        let commands: Vec<TelegramBotCommand> = scope_commands
+            .iter()
+            .map(|info| TelegramBotCommand{
+                command: info.name.to_string(),
+                description: info.description.to_string()
+            })
+            .collect();
+        client
+            .set_my_commands(&commands, scope, None)
+            .await?;
+
+        // Переводы, для отсутствующих используем описание по-умолчанию
+        let mut languages: Vec<&str> = scope_commands
+            .iter()
+            .flat_map(|info| info.localized_descriptions.iter().map(|(lang, _)| *lang))
+            .collect();
+        languages.sort_unstable();
+        languages.dedup();
+
+        for language in languages {
+            let commands: Vec<TelegramBotCommand> = scope_commands
+                .iter()
+                .map(|info|{
+                    let description = info.localized_descriptions
+                        .iter()
+                        .find(|(lang, _)| lang.eq(&language))
+                        .map(|(_, description)| *description)
+                        .unwrap_or(info.description);
+                    TelegramBotCommand{
+                        command: info.name.to_string(),
+                        description: description.to_string()
+                    }
+                })
+                .collect();
+            client
+                .set_my_commands(&commands, scope, Some(language))
+                .await?;
+        }
+
+        debug!("Commands registered for scope {:?}: {}", scope, commands.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc
+        }
+    };
+    use serde_json::{
+        json
+    };
+    use crate::{
+        telegram_client::{
+            TelegramTransport,
+            TelegramFakeTransport,
+            TelegramRecordReplayTransport
+        }
+    };
+    use super::*;
+
+    #[tokio::test]
+    async fn test_register_commands_with_fake_transport() {
+        let transport = Arc::new(TelegramFakeTransport::default());
+        // Для каждой области меню по-умолчанию и русский перевод
+        for _ in 0..REGISTERED_SCOPES.len() * 2 {
+            transport.push_result("setMyCommands", json!(true));
+        }
+        let client = TelegramClient::with_transport(transport.clone());
+
+        register_bot_commands(&client).await.unwrap();
+
+        let calls = transport.calls();
+        assert_eq!(calls.len(), REGISTERED_SCOPES.len() * 2);
+        assert!(calls.iter().all(|(method, _)| method == "setMyCommands"));
+
+        let (_, private_default) = &calls[0];
+        assert_eq!(private_default["scope"], json!({"type": "all_private_chats"}));
+        assert!(private_default.get("language_code").is_none());
+        assert_eq!(private_default["commands"][0], json!({
+            "command": "start",
+            "description": "Connect your Pocket account"
+        }));
+
+        let (_, private_ru) = &calls[1];
+        assert_eq!(private_ru["language_code"], json!("ru"));
+
+        // В группах нет команд для подключения аккаунта, но есть действия с элементами
+        let (_, group_default) = &calls[2];
+        assert_eq!(group_default["scope"], json!({"type": "all_group_chats"}));
+        let group_commands: Vec<&str> = group_default["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|command| command["command"].as_str().unwrap())
+            .collect();
+        assert!(!group_commands.contains(&"start"));
+        assert!(group_commands.contains(&"archive"));
+    }
+
+    #[tokio::test]
+    async fn test_register_commands_with_api_error() {
+        let transport = Arc::new(TelegramFakeTransport::default());
+        transport.push_error("setMyCommands", 400, "Bad Request: BOT_COMMAND_INVALID");
+        let client = TelegramClient::with_transport(transport.clone());
+
+        match register_bot_commands(&client).await {
+            Err(TelegramBotError::ApiError(err)) => assert_eq!(err.error_code, 400),
+            other => panic!("Unexpected result: {:?}", other)
+        }
+        // После первой ошибки регистрация прерывается
+        assert_eq!(transport.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_register_commands_with_replay_transport() {
+        let fixture_path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/register_bot_commands.jsonl");
+        let transport = Arc::new(TelegramRecordReplayTransport::replay(fixture_path).unwrap());
+        let client = TelegramClient::with_transport(transport.clone());
+
+        register_bot_commands(&client).await.unwrap();
+
+        // Все записанные вызовы использованы, следующий уже не из записи
+        assert!(transport.call("getMe", &json!({})).await.is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use tracing::{
+    instrument,
+    debug,
+    warn,
+    error
+};
+use tap::{
+    prelude::{
+        *
+    }
+};
+use crate::{
+    app::{
+        Application
+    },
+    error::{
+        TelegramBotError
+    },
+    telegram_client::{
+        TelegramChatId,
+        TelegramFormattedText,
+        TelegramOutgoingText,
+        TelegramParseMode
+    },
+    model::{
+        UserStateKind,
+        UserStats
+    }
+};
+
+fn dashboard_text(state: UserStateKind, stats: &UserStats) -> TelegramOutgoingText {
+    let account = match state {
+        UserStateKind::Unauthorized => "not connected",
+        UserStateKind::AutorizationConfirmationWaiting => "waiting for confirmation",
+        UserStateKind::Authorized => "connected"
+    };
+
+    let mut text = TelegramFormattedText::new(TelegramParseMode::Html)
+        .bold("Pocket: ")
+        .text(account)
+        .new_line()
+        .bold("Saved today: ")
+        .text(format!("{}", stats.saved_today()));
+    if stats.shared_today() > 0 {
+        text = text
+            .new_line()
+            .bold("Shared today: ")
+            .text(format!("{}", stats.shared_today()));
+    }
+    if let Some(title) = stats.last_saved_title.as_ref() {
+        text = text
+            .new_line()
+            .bold("Last saved: ")
+            .italic(title.as_str());
+    }
+    if !stats.failed_saves.is_empty() {
+        text = text
+            .new_line()
+            .bold("Recent failed saves:");
+        for link in stats.failed_saves.iter() {
+            text = text
+                .new_line()
+                .text("• ")
+                .link(link.as_str(), link.as_str());
+        }
+    }
+
+    TelegramOutgoingText::from(text)
+        .without_link_preview()
+}
+
+/// Новое закрепленное сообщение со статусом, предыдущее удаляется
+#[instrument(skip(app))]
+pub async fn create_dashboard(app: &Application, chat_id: TelegramChatId) -> Result<(), TelegramBotError> {
+    let old_message_id = app
+        .redis_client
+        .get_dashboard_message_id(chat_id)
+        .await?;
+    if let Some(old_message_id) = old_message_id {
+        // Старое сообщение могли уже удалить руками
+        app
+            .telegram_client
+            .message_handle(chat_id, old_message_id)
+            .delete()
+            .await
+            .tap_err(|e|{ warn!("Old dashboard delete error: {}", e) })
+            .ok();
+    }
+
+    let state = app
+        .redis_client
+        .get_user_state(chat_id)
+        .await?;
+    let stats = app
+        .redis_client
+        .get_user_stats(chat_id)
+        .await?;
+
+    let message = app
+        .telegram_client
+        .send_text(chat_id, dashboard_text(state.kind(), &stats))
+        .await
+        .tap_err(|e|{ error!("Dashboard send error: {}", e) })?;
+    app
+        .redis_client
+        .set_dashboard_message_id(chat_id, Some(message.message_id))
+        .await?;
+
+    // В группе без прав администратора закрепить не выйдет, но сообщение все равно обновляется
+    message
+        .handle()
+        .pin(true)
+        .await
+        .tap_err(|e|{ warn!("Dashboard pin error: {}", e) })
+        .ok();
+
+    Ok(())
+}
+
+/// Обновление закрепленного сообщения, если оно есть.
+/// Ошибки только логируются: статус не должен мешать основной работе.
+#[instrument(skip(app))]
+pub async fn refresh_dashboard(app: &Application, chat_id: TelegramChatId) {
+    let result: Result<(), TelegramBotError> = async {
+        let message_id = match app.redis_client.get_dashboard_message_id(chat_id).await? {
+            Some(message_id) => message_id,
+            None => return Ok(())
+        };
+
+        let state = app
+            .redis_client
+            .get_user_state(chat_id)
+            .await?;
+        let stats = app
+            .redis_client
+            .get_user_stats(chat_id)
+            .await?;
+
+        let result = app
+            .telegram_client
+            .message_handle(chat_id, message_id)
+            .edit_text(dashboard_text(state.kind(), &stats))
+            .await;
+        match result {
+            Ok(_) => Ok(()),
+            // Текст не поменялся, это не ошибка
+            Err(TelegramBotError::ApiError(err)) if err.is_not_modified() => Ok(()),
+            // Сообщение удалили, больше не обновляем
+            Err(TelegramBotError::ApiError(err)) if err.description.contains("message to edit not found") => {
+                debug!("Dashboard message is deleted");
+                app
+                    .redis_client
+                    .set_dashboard_message_id(chat_id, None)
+                    .await
+            },
+            Err(err) => Err(err)
+        }
+    }.await;
+
+    if let Err(err) = result {
+        warn!("Dashboard refresh error: {}", err);
+    }
+}
+
+/// Изменение статистики и обновление закрепленного сообщения
+#[instrument(skip(app, update))]
+pub async fn update_stats<F>(app: &Application, chat_id: TelegramChatId, update: F) -> Result<(), TelegramBotError>
+where
+    F: FnOnce(&mut UserStats)
+{
+    let mut stats = app
+        .redis_client
+        .get_user_stats(chat_id)
+        .await?;
+    update(&mut stats);
+    app
+        .redis_client
+        .set_user_stats(chat_id, &stats)
+        .await?;
+
+    refresh_dashboard(app, chat_id).await;
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use tracing::{
+    instrument,
+    debug,
+    error
+};
+use tap::{
+    prelude::{
+        *
+    }
+};
+use crate::{
+    app::{
+        Application
+    },
+    error::{
+        TelegramBotError
+    },
+    telegram_client::{
+        TelegramChatId,
+        TelegramDocumentData,
+        TelegramChatAction,
+        TelegramFormattedText,
+        TelegramParseMode,
+        TelegramLinkPreviewOptions
+    },
+    pocket_items::{
+        PocketAction
+    }
+};
+use super::{
+    dashboard::{
+        update_stats
+    }
+};
+
+/// Экспорт закладок или список ссылок больше мегабайта не ожидаем
+const IMPORT_FILE_MAX_SIZE: u64 = 1024 * 1024;
+
+/// Сколько ссылок отправляем в Pocket одним запросом
+const IMPORT_BATCH_SIZE: usize = 100;
+
+/// Символы, на которых заканчивается ссылка в тексте или HTML атрибуте
+const LINK_TERMINATORS: &[char] = &['"', '\'', '<', '>', '`'];
+
+/// Принимаем только текстовые файлы и HTML экспорт закладок из браузера
+fn is_supported_document(document: &TelegramDocumentData) -> bool {
+    let by_mime = document
+        .mime_type
+        .as_deref()
+        .map(|mime| mime == "text/plain" || mime == "text/html")
+        .unwrap_or(false);
+    let by_name = document
+        .file_name
+        .as_deref()
+        .map(|name| name.to_lowercase())
+        .map(|name| name.ends_with(".txt") || name.ends_with(".html") || name.ends_with(".htm"))
+        .unwrap_or(false);
+    by_mime || by_name
+}
+
+/// Все http(s) ссылки из текста файла, порядок сохраняется, дубликаты отбрасываются
+fn extract_links_from_text(text: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    for (start, _) in text.match_indices("http") {
+        let rest = &text[start..];
+        if !rest.starts_with("http://") && !rest.starts_with("https://") {
+            continue;
+        }
+        let end = rest
+            .find(|c: char| c.is_whitespace() || LINK_TERMINATORS.contains(&c))
+            .unwrap_or(rest.len());
+        let link = rest[..end]
+            .trim_end_matches(&['.', ',', ')', ';'][..])
+            .replace("&amp;", "&");
+        if validator::validate_url(&link) && !links.contains(&link) {
+            links.push(link);
+        }
+    }
+    links
+}
+
+/// Сохранение в Pocket всех ссылок из присланного файла
+#[instrument(skip(app, pocket_api_token))]
+pub async fn import_links_from_document(app: &Application,
+                                        pocket_api_token: &str,
+                                        chat_id: TelegramChatId,
+                                        document: &TelegramDocumentData) -> Result<(), TelegramBotError> {
+    if !is_supported_document(document) {
+        app
+            .telegram_client
+            .send_message(chat_id, "Send a .txt or .html file with links".to_string())
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+        return Ok(());
+    }
+
+    let download = async {
+        // Размер известен заранее, слишком большой файл даже не запрашиваем
+        if document.file_size.filter(|size| *size > IMPORT_FILE_MAX_SIZE).is_some() {
+            return Err(TelegramBotError::FileTooLarge(IMPORT_FILE_MAX_SIZE));
+        }
+        let file = app
+            .telegram_client
+            .get_file(&document.file_id)
+            .await?;
+        app
+            .telegram_client
+            .download_file(&file, IMPORT_FILE_MAX_SIZE)
+            .await
+    };
+    let data = match app.telegram_client.with_chat_action(chat_id, TelegramChatAction::Typing, download).await {
+        Ok(data) => data,
+        Err(TelegramBotError::FileTooLarge(limit)) => {
+            app
+                .telegram_client
+                .send_message(chat_id, format!("File is too large, maximum size is {} KB", limit / 1024))
+                .await
+                .tap_err(|e|{ error!("Message send error: {}", e) })?;
+            return Ok(());
+        },
+        Err(err) => {
+            error!("File download error: {}", err);
+            return Err(err);
+        }
+    };
+
+    let links = extract_links_from_text(&String::from_utf8_lossy(&data));
+    debug!("Links found in file: {}", links.len());
+    if links.is_empty() {
+        app
+            .telegram_client
+            .send_message(chat_id, "No links found in file".to_string())
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+        return Ok(());
+    }
+
+    let mut failed_links: Vec<&str> = Vec::new();
+    for batch in links.chunks(IMPORT_BATCH_SIZE) {
+        let actions: Vec<PocketAction> = batch
+            .iter()
+            .map(|link| PocketAction::Add{
+                url: link.clone(),
+                tags: None
+            })
+            .collect();
+        let results = app
+            .telegram_client
+            .with_chat_action(chat_id, TelegramChatAction::Typing, app
+                .pocket_items
+                .send(pocket_api_token, &actions))
+            .await
+            .tap_err(|e|{ error!("Pocket batch append error: {}", e) })?;
+        // Результатов может прийти меньше, чем действий, недостающие считаем неудачными
+        failed_links.extend(batch
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !results.get(*index).copied().unwrap_or(false))
+            .map(|(_, link)| link.as_str()));
+    }
+    let saved_count = links.len() - failed_links.len();
+
+    update_stats(app, chat_id, |stats| stats.record_imported(saved_count as u32)).await?;
+
+    // Список неудачных ссылок бывает длинным, поэтому отчет может уйти несколькими сообщениями
+    let mut report = TelegramFormattedText::new(TelegramParseMode::Html)
+        .text(format!("Imported {} of {} links", saved_count, links.len()));
+    if !failed_links.is_empty() {
+        report = report
+            .new_line()
+            .new_line()
+            .bold("Failed:");
+        for link in failed_links {
+            report = report
+                .new_line()
+                .text(link);
+        }
+    }
+    app
+        .telegram_client
+        .send_text_chunked(chat_id, report, Some(TelegramLinkPreviewOptions::disabled()), None)
+        .await
+        .tap_err(|e|{ error!("Message send error: {}", e) })?;
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::{
+    sync::{
+        Arc
+    }
+};
+use tracing::{
+    instrument,
+    debug,
+    info,
+    error
+};
+use tap::{
+    prelude::{
+        *
+    }
+};
+use crate::{
+    app::{
+        Application
+    },
+    error::{
+        TelegramBotError
+    },
+    telegram_client::{
+        TelegramInlineQueryData,
+        TelegramChosenInlineResultData,
+        TelegramInlineQueryResultArticle,
+        TelegramInlineQueryResultsButton,
+        TelegramFormattedText,
+        TelegramOutgoingText,
+        TelegramParseMode
+    },
+    pocket_items::{
+        PocketItem,
+        PocketGetQuery
+    },
+    model::{
+        UserState
+    }
+};
+use super::{
+    dashboard::{
+        update_stats
+    }
+};
+
+/// Сколько результатов отдаем за один запрос, больше 50 Telegram не принимает
+const INLINE_RESULTS_PER_PAGE: usize = 20;
+
+/// Результаты персональные, поэтому кешируем ненадолго
+const INLINE_RESULTS_CACHE_TIME: u32 = 10;
+
+/// Параметр `/start`, с которым пользователь приходит из inline режима
+const INLINE_START_PARAMETER: &str = "inline";
+
+/// Статья для inline ответа, при выборе в чат уходит ссылка с заголовком
+fn item_article(item: &PocketItem) -> Option<TelegramInlineQueryResultArticle> {
+    let id = item.item_id.clone()?;
+    let url = item.url()?;
+    let title = item.title().unwrap_or(url);
+
+    let mut text = TelegramFormattedText::new(TelegramParseMode::Html)
+        .link(title, url);
+    if let Some(summary) = item.summary() {
+        text = text
+            .new_line()
+            .italic(summary);
+    }
+
+    let mut article = TelegramInlineQueryResultArticle::new(
+        id,
+        title.to_string(),
+        TelegramOutgoingText::from(text).into()
+    );
+    article.url = Some(url.to_string());
+    article.description = item
+        .summary()
+        .or_else(|| item.excerpt.clone());
+
+    Some(article)
+}
+
+#[instrument(skip(app))]
+async fn answer_inline_query(app: &Application, query: TelegramInlineQueryData) -> Result<(), TelegramBotError> {
+    let state = app
+        .redis_client
+        .get_user_state(query.from.id)
+        .await?;
+
+    // Без подключенного Pocket искать негде, предлагаем перейти в чат с ботом
+    let token = match state {
+        UserState::Authorized{pocket_api_token} => pocket_api_token,
+        _ => {
+            let button = TelegramInlineQueryResultsButton{
+                text: "Connect your Pocket account".to_string(),
+                start_parameter: INLINE_START_PARAMETER.to_string()
+            };
+            return app
+                .telegram_client
+                .answer_inline_query(query.id, &[], INLINE_RESULTS_CACHE_TIME, true, None, Some(button))
+                .await;
+        }
+    };
+
+    // Offset передается Telegram обратно как есть при прокрутке результатов
+    let offset: usize = query.offset.parse().unwrap_or(0);
+    let search = query.query.trim();
+    let items = app
+        .pocket_items
+        .get(&token, &PocketGetQuery{
+            search: if search.is_empty() { None } else { Some(search.to_string()) },
+            count: Some(INLINE_RESULTS_PER_PAGE),
+            offset: Some(offset),
+            ..Default::default()
+        })
+        .await
+        .tap_err(|e|{ error!("Pocket items search failed: {}", e) })?;
+    debug!("Pocket items found: {}", items.len());
+
+    let next_offset = if items.len() < INLINE_RESULTS_PER_PAGE {
+        None
+    }else{
+        Some(format!("{}", offset + items.len()))
+    };
+
+    let results: Vec<TelegramInlineQueryResultArticle> = items
+        .iter()
+        .filter_map(item_article)
+        .collect();
+
+    app
+        .telegram_client
+        .answer_inline_query(query.id, &results, INLINE_RESULTS_CACHE_TIME, true, next_offset, None)
+        .await
+}
+
+/// Inline запросы не зависят от диалога с пользователем и ждать не могут,
+/// поэтому обрабатываются сразу, без персонального обработчика
+#[instrument(skip(app, query), fields(user_id = query.from.id))]
+pub async fn process_inline_query(app: Arc<Application>, query: TelegramInlineQueryData) {
+    if let Err(err) = answer_inline_query(&app, query).await {
+        error!("Inline query processing error: {:?}", err);
+    }
+}
+
+/// Пользователь отправил один из результатов в чат, учитываем это в его статистике.
+/// Личный чат с ботом совпадает с идентификатором пользователя.
+#[instrument(skip(app, result), fields(user_id = result.from.id))]
+pub async fn process_chosen_inline_result(app: Arc<Application>, result: TelegramChosenInlineResultData) {
+    info!("Item {} shared by query: {}", result.result_id, result.query);
+
+    if let Err(err) = update_stats(&app, result.from.id, |stats| stats.record_shared()).await {
+        error!("Shared item stats update error: {:?}", err);
+    }
+}
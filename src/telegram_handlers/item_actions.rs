@@ -0,0 +1,243 @@
+use tracing::{
+    instrument,
+    debug,
+    error
+};
+use tap::{
+    prelude::{
+        *
+    }
+};
+use crate::{
+    app::{
+        Application
+    },
+    error::{
+        TelegramBotError
+    },
+    telegram_client::{
+        TelegramChatId,
+        TelegramMessageId,
+        TelegramChatAction,
+        TelegramInlineKeyboardMarkup,
+        TelegramInlineKeyboardButton
+    },
+    pocket_items::{
+        PocketAction
+    },
+    model::{
+        SavedItem
+    }
+};
+use super::{
+    callback_data::{
+        CallbackAction
+    }
+};
+
+/// Действие с сохраненным элементом, доступно и командой, и кнопкой
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemAction {
+    Archive,
+    Readd,
+    Favorite,
+    Unfavorite,
+    Delete
+}
+
+impl ItemAction {
+    /// Короткий код для `callback_data`
+    pub fn code(self) -> &'static str {
+        match self {
+            ItemAction::Archive => "arc",
+            ItemAction::Readd => "readd",
+            ItemAction::Favorite => "fav",
+            ItemAction::Unfavorite => "unfav",
+            ItemAction::Delete => "del"
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<ItemAction> {
+        match code {
+            "arc" => Some(ItemAction::Archive),
+            "readd" => Some(ItemAction::Readd),
+            "fav" => Some(ItemAction::Favorite),
+            "unfav" => Some(ItemAction::Unfavorite),
+            "del" => Some(ItemAction::Delete),
+            _ => None
+        }
+    }
+
+    pub fn pocket_action(self, item_id: String) -> PocketAction {
+        match self {
+            ItemAction::Archive => PocketAction::Archive{item_id},
+            ItemAction::Readd => PocketAction::Readd{item_id},
+            ItemAction::Favorite => PocketAction::Favorite{item_id},
+            ItemAction::Unfavorite => PocketAction::Unfavorite{item_id},
+            ItemAction::Delete => PocketAction::Delete{item_id}
+        }
+    }
+
+    /// Уведомление об успешном выполнении
+    pub fn done_text(self) -> &'static str {
+        match self {
+            ItemAction::Archive => "Archived",
+            ItemAction::Readd => "Moved back to unread",
+            ItemAction::Favorite => "Added to favorites",
+            ItemAction::Unfavorite => "Removed from favorites",
+            ItemAction::Delete => "Deleted"
+        }
+    }
+
+    /// Состояние элемента после действия, после удаления элемента больше нет
+    fn apply(self, mut item: SavedItem) -> Option<SavedItem> {
+        match self {
+            ItemAction::Archive => item.archived = true,
+            ItemAction::Readd => item.archived = false,
+            ItemAction::Favorite => item.favorite = true,
+            ItemAction::Unfavorite => item.favorite = false,
+            ItemAction::Delete => return None
+        }
+        Some(item)
+    }
+}
+
+/// Кнопки под подтверждением сохранения
+pub fn saved_item_buttons(item: &SavedItem) -> TelegramInlineKeyboardMarkup {
+    let item_id = item.item_id.clone();
+    let archive = if item.archived {
+        TelegramInlineKeyboardButton::callback("Re-add", CallbackAction::Item(ItemAction::Readd, item_id.clone()).to_data())
+    } else {
+        TelegramInlineKeyboardButton::callback("Archive", CallbackAction::Item(ItemAction::Archive, item_id.clone()).to_data())
+    };
+    let favorite = if item.favorite {
+        TelegramInlineKeyboardButton::callback("★ Unfavorite", CallbackAction::Item(ItemAction::Unfavorite, item_id.clone()).to_data())
+    } else {
+        TelegramInlineKeyboardButton::callback("☆ Favorite", CallbackAction::Item(ItemAction::Favorite, item_id.clone()).to_data())
+    };
+    let delete = TelegramInlineKeyboardButton::callback("Delete", CallbackAction::Item(ItemAction::Delete, item_id).to_data());
+    TelegramInlineKeyboardMarkup::single_row(vec![archive, favorite, delete])
+}
+
+/// Действие сразу для нескольких элементов одним запросом к Pocket.
+/// Для каждого элемента возвращается признак успеха, порядок сохраняется.
+#[instrument(skip(app, pocket_api_token))]
+pub async fn send_item_actions(app: &Application,
+                               pocket_api_token: &str,
+                               action: ItemAction,
+                               item_ids: &[String]) -> Result<Vec<bool>, TelegramBotError> {
+    let actions: Vec<PocketAction> = item_ids
+        .iter()
+        .map(|item_id| action.pocket_action(item_id.clone()))
+        .collect();
+    app
+        .pocket_items
+        .send(pocket_api_token, &actions)
+        .await
+        .tap_err(|e|{ error!("Pocket actions error: {}", e) })
+}
+
+/// Обновление кнопок под подтверждением сохранения после действия.
+/// Если сообщение не подтверждение, ничего не делаем.
+#[instrument(skip(app))]
+pub async fn update_saved_item_message(app: &Application,
+                                       chat_id: TelegramChatId,
+                                       message_id: TelegramMessageId,
+                                       action: ItemAction) -> Result<(), TelegramBotError> {
+    let item = match app.redis_client.get_saved_item(chat_id, message_id).await? {
+        Some(item) => item,
+        None => return Ok(())
+    };
+
+    let item = action.apply(item);
+    app
+        .redis_client
+        .set_saved_item(chat_id, message_id, item.as_ref())
+        .await?;
+
+    let result = app
+        .telegram_client
+        .message_handle(chat_id, message_id)
+        .edit_reply_markup(item.as_ref().map(saved_item_buttons))
+        .await;
+    match result {
+        Ok(_) => Ok(()),
+        // Повторное действие с тем же элементом
+        Err(TelegramBotError::ApiError(err)) if err.is_not_modified() => Ok(()),
+        Err(err) => Err(err)
+    }
+}
+
+/// Действие командой: с элементом из подтверждения, на которое ответили,
+/// иначе со всеми ссылками из последнего сохранения
+#[instrument(skip(app, pocket_api_token))]
+pub async fn apply_item_action_command(app: &Application,
+                                       pocket_api_token: &str,
+                                       chat_id: TelegramChatId,
+                                       reply_to_message_id: Option<TelegramMessageId>,
+                                       action: ItemAction) -> Result<(), TelegramBotError> {
+    let message_ids = match reply_to_message_id {
+        Some(message_id) => vec![message_id],
+        None => app
+            .redis_client
+            .get_last_saved_messages(chat_id)
+            .await
+            .tap_err(|e|{ error!("Get last saved messages error: {}", e) })?
+    };
+
+    let mut targets: Vec<(TelegramMessageId, String)> = Vec::new();
+    for message_id in message_ids {
+        let item = app
+            .redis_client
+            .get_saved_item(chat_id, message_id)
+            .await
+            .tap_err(|e|{ error!("Get saved item error: {}", e) })?;
+        if let Some(item) = item {
+            targets.push((message_id, item.item_id));
+        }
+    }
+    debug!("Items for action: {}", targets.len());
+
+    if targets.is_empty() {
+        app
+            .telegram_client
+            .send_message(chat_id, "Reply to a save confirmation or save a link first".to_string())
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+        return Ok(());
+    }
+
+    let item_ids: Vec<String> = targets
+        .iter()
+        .map(|(_, item_id)| item_id.clone())
+        .collect();
+    let results = app
+        .telegram_client
+        .with_chat_action(chat_id, TelegramChatAction::Typing, send_item_actions(app, pocket_api_token, action, &item_ids))
+        .await?;
+
+    let mut done_count = 0;
+    for ((message_id, _), done) in targets.iter().zip(results.iter()) {
+        if !done {
+            continue;
+        }
+        done_count += 1;
+        update_saved_item_message(app, chat_id, *message_id, action)
+            .await
+            .tap_err(|e|{ error!("Saved item message update error: {}", e) })
+            .ok();
+    }
+
+    let text = if done_count == targets.len() && done_count == 1 {
+        action.done_text().to_string()
+    } else {
+        format!("{}: {} of {}", action.done_text(), done_count, targets.len())
+    };
+    app
+        .telegram_client
+        .send_message(chat_id, text)
+        .await
+        .tap_err(|e|{ error!("Message send error: {}", e) })?;
+
+    Ok(())
+}
@@ -0,0 +1,280 @@
+use tracing::{
+    instrument,
+    debug,
+    error
+};
+use tap::{
+    prelude::{
+        *
+    }
+};
+use crate::{
+    app::{
+        Application
+    },
+    error::{
+        TelegramBotError
+    },
+    telegram_client::{
+        TelegramChatId,
+        TelegramMessageId,
+        TelegramFormattedText,
+        TelegramOutgoingText,
+        TelegramParseMode,
+        TelegramChatAction,
+        TelegramInlineKeyboardMarkup,
+        TelegramInlineKeyboardButton,
+        TelegramLinkPreviewOptions,
+        TelegramReplyMarkup
+    },
+    pocket_items::{
+        PocketItem,
+        PocketGetQuery,
+        PocketItemState
+    }
+};
+use super::{
+    callback_data::{
+        CallbackAction
+    },
+    item_actions::{
+        ItemAction
+    },
+    search_query::{
+        SearchQuery
+    }
+};
+
+/// Сколько элементов показываем на одной странице
+const ITEMS_PAGE_SIZE: usize = 10;
+
+/// Максимальная длина заголовка элемента в символах
+const ITEM_TITLE_MAX_LENGTH: usize = 120;
+
+/// Откуда берутся элементы для страницы
+#[derive(Debug)]
+pub enum ItemsSource<'a> {
+    /// Непрочитанное, `/list`
+    Unread,
+    /// Результаты `/search`
+    Search(&'a SearchQuery)
+}
+
+impl<'a> ItemsSource<'a> {
+    fn get_query(&self, count: usize, offset: usize) -> PocketGetQuery {
+        match self {
+            ItemsSource::Unread => PocketGetQuery{
+                state: Some(PocketItemState::Unread),
+                count: Some(count),
+                offset: Some(offset),
+                ..Default::default()
+            },
+            ItemsSource::Search(query) => query.to_get_query(count, offset)
+        }
+    }
+
+    fn heading(&self, number: usize) -> String {
+        match self {
+            ItemsSource::Unread => format!("Unread items, page {}", number + 1),
+            ItemsSource::Search(_) => format!("Search results, page {}", number + 1)
+        }
+    }
+
+    fn empty_text(&self, number: usize) -> &'static str {
+        match (self, number) {
+            (ItemsSource::Unread, 0) => "Your reading list is empty",
+            (ItemsSource::Unread, _) => "No more unread items",
+            (ItemsSource::Search(_), 0) => "Nothing found",
+            (ItemsSource::Search(_), _) => "No more results"
+        }
+    }
+
+    fn page_action(&self, number: usize) -> CallbackAction {
+        match self {
+            ItemsSource::Unread => CallbackAction::ListPage(number),
+            ItemsSource::Search(_) => CallbackAction::SearchPage(number)
+        }
+    }
+
+    /// Кнопки действий для каждого элемента показываем только в поиске,
+    /// в списке непрочитанного они бы закрыли собой сам список
+    fn has_item_buttons(&self) -> bool {
+        matches!(self, ItemsSource::Search(_))
+    }
+}
+
+/// Страница элементов
+struct ItemsPage {
+    number: usize,
+    items: Vec<PocketItem>,
+    has_next: bool
+}
+
+/// Кнопки действий с элементом: открыть, архив, избранное и удаление
+fn item_buttons(number: usize, item: &PocketItem) -> Option<Vec<TelegramInlineKeyboardButton>> {
+    let item_id = item.item_id.clone()?;
+    let mut buttons = Vec::new();
+    if let Some(url) = item.url() {
+        buttons.push(TelegramInlineKeyboardButton::url(format!("{}. Open", number), url));
+    }
+    let (archive, archive_text) = if item.is_archived() {
+        (ItemAction::Readd, "Re-add")
+    } else {
+        (ItemAction::Archive, "Archive")
+    };
+    let (favorite, favorite_text) = if item.is_favorite() {
+        (ItemAction::Unfavorite, "★")
+    } else {
+        (ItemAction::Favorite, "☆")
+    };
+    buttons.push(TelegramInlineKeyboardButton::callback(archive_text, CallbackAction::Item(archive, item_id.clone()).to_data()));
+    buttons.push(TelegramInlineKeyboardButton::callback(favorite_text, CallbackAction::Item(favorite, item_id.clone()).to_data()));
+    buttons.push(TelegramInlineKeyboardButton::callback("Delete", CallbackAction::Item(ItemAction::Delete, item_id).to_data()));
+    Some(buttons)
+}
+
+/// Заголовок обрезается, чтобы страница целиком влезала в одно сообщение
+/// и ее можно было заменять при перелистывании
+fn short_title(item: &PocketItem) -> String {
+    let title = item
+        .title()
+        .or_else(|| item.url())
+        .unwrap_or("Untitled");
+    if title.chars().count() <= ITEM_TITLE_MAX_LENGTH {
+        return title.to_string();
+    }
+    let mut short: String = title
+        .chars()
+        .take(ITEM_TITLE_MAX_LENGTH - 1)
+        .collect();
+    short.push('…');
+    short
+}
+
+/// Текст страницы и кнопки под ним
+fn page_content(source: &ItemsSource<'_>, page: &ItemsPage) -> (TelegramFormattedText, Option<TelegramInlineKeyboardMarkup>) {
+    if page.items.is_empty() {
+        let text = TelegramFormattedText::new(TelegramParseMode::Html)
+            .text(source.empty_text(page.number));
+        return (text, None);
+    }
+
+    let mut rows = Vec::new();
+    let mut text = TelegramFormattedText::new(TelegramParseMode::Html)
+        .bold(source.heading(page.number));
+    for (index, item) in page.items.iter().enumerate() {
+        let number = page.number * ITEMS_PAGE_SIZE + index + 1;
+        let title = short_title(item);
+        text = text
+            .new_line()
+            .text(format!("{}. ", number));
+        text = match item.url() {
+            Some(url) => text.link(title, url),
+            None => text.text(title)
+        };
+        if let Some(domain) = item.domain() {
+            text = text
+                .text(" - ")
+                .italic(domain);
+        }
+
+        if source.has_item_buttons() {
+            rows.extend(item_buttons(number, item));
+        }
+    }
+
+    let mut navigation = Vec::new();
+    if page.number > 0 {
+        navigation.push(TelegramInlineKeyboardButton::callback("« Prev", source.page_action(page.number - 1).to_data()));
+    }
+    if page.has_next {
+        navigation.push(TelegramInlineKeyboardButton::callback("Next »", source.page_action(page.number + 1).to_data()));
+    }
+    if !navigation.is_empty() {
+        rows.push(navigation);
+    }
+
+    if rows.is_empty() {
+        (text, None)
+    } else {
+        (text, Some(TelegramInlineKeyboardMarkup::new(rows)))
+    }
+}
+
+/// Загрузка страницы, лишний элемент запрашиваем чтобы знать, есть ли следующая
+#[instrument(skip(app, pocket_api_token))]
+async fn load_page(app: &Application,
+                   pocket_api_token: &str,
+                   chat_id: TelegramChatId,
+                   source: &ItemsSource<'_>,
+                   number: usize) -> Result<ItemsPage, TelegramBotError> {
+    let mut items = app
+        .telegram_client
+        .with_chat_action(chat_id, TelegramChatAction::Typing, app
+            .pocket_items
+            .get(pocket_api_token, &source.get_query(ITEMS_PAGE_SIZE + 1, number * ITEMS_PAGE_SIZE)))
+        .await
+        .tap_err(|e|{ error!("Pocket items list failed: {}", e) })?;
+    debug!("Pocket items received: {}", items.len());
+
+    let has_next = items.len() > ITEMS_PAGE_SIZE;
+    items.truncate(ITEMS_PAGE_SIZE);
+
+    Ok(ItemsPage{
+        number,
+        items,
+        has_next
+    })
+}
+
+/// Новое сообщение с первой страницей списка
+#[instrument(skip(app, pocket_api_token))]
+pub async fn send_items_list(app: &Application,
+                             pocket_api_token: &str,
+                             chat_id: TelegramChatId,
+                             source: &ItemsSource<'_>) -> Result<TelegramMessageId, TelegramBotError> {
+    let page = load_page(app, pocket_api_token, chat_id, source, 0).await?;
+    let (text, buttons) = page_content(source, &page);
+    let messages = app
+        .telegram_client
+        .send_text_chunked(chat_id, text, Some(TelegramLinkPreviewOptions::disabled()), buttons.map(TelegramReplyMarkup::from))
+        .await
+        .tap_err(|e|{ error!("Message send error: {}", e) })?;
+
+    // Кнопки прикреплены к последнему сообщению, его и будем менять при перелистывании
+    messages
+        .last()
+        .map(|message| message.message_id)
+        .ok_or(TelegramBotError::EmptyMessageError)
+}
+
+/// Показ страницы в уже отправленном сообщении
+#[instrument(skip(app, pocket_api_token))]
+pub async fn edit_items_list(app: &Application,
+                             pocket_api_token: &str,
+                             chat_id: TelegramChatId,
+                             message_id: TelegramMessageId,
+                             source: &ItemsSource<'_>,
+                             number: usize) -> Result<(), TelegramBotError> {
+    let page = load_page(app, pocket_api_token, chat_id, source, number).await?;
+    let (text, buttons) = page_content(source, &page);
+    let mut text = TelegramOutgoingText::from(text)
+        .without_link_preview();
+    if let Some(buttons) = buttons {
+        text = text.with_reply_markup(buttons);
+    }
+    let result = app
+        .telegram_client
+        .message_handle(chat_id, message_id)
+        .edit_text(text)
+        .await;
+    match result {
+        Ok(_) => Ok(()),
+        // Двойное нажатие на кнопку
+        Err(TelegramBotError::ApiError(err)) if err.is_not_modified() => Ok(()),
+        Err(err) => {
+            error!("Message edit error: {}", err);
+            Err(err)
+        }
+    }
+}
@@ -1,8 +1,30 @@
 mod user_message;
+mod user_event;
 mod process_loop;
+mod commands;
+mod router;
+mod inline_query;
+mod file_import;
+mod dashboard;
+mod callback_data;
+mod item_list;
+mod search_query;
+mod callback_query;
+mod item_actions;
 
 pub use self::{
     process_loop::{
-        telegram_receive_updates_loop
+        telegram_receive_updates_loop,
+        process_telegram_update,
+        TELEGRAM_ALLOWED_UPDATES
+    },
+    user_event::{
+        UserEvent
+    },
+    commands::{
+        register_bot_commands
+    },
+    dashboard::{
+        refresh_dashboard
     }
 };
\ No newline at end of file
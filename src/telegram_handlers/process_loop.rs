@@ -22,56 +22,162 @@ use crate::{
     },
     telegram_client::{
         TelegramMessageData,
-        TelegramUserId
+        TelegramCallbackQueryData,
+        TelegramUpdateData,
+        TelegramUpdateKind,
+        TelegramChatId
     }
 };
 use super::{
     user_message::{
         user_message_processing_loop
+    },
+    user_event::{
+        UserEvent
+    },
+    inline_query::{
+        process_inline_query,
+        process_chosen_inline_result
     }
 };
 
+/// Типы обновлений, которые мы обрабатываем, остальные Telegram не будет присылать
+pub const TELEGRAM_ALLOWED_UPDATES: &[&str] = &[
+    "message",
+    "callback_query",
+    "inline_query",
+    "chosen_inline_result"
+];
+
 /// Данный метод нужен лишь для того, чтобы спокойно отлавливать ошибки и логировать их этой корутине
-#[instrument(skip(app, sub), fields(user_id = sub.get_key()))]
-async fn start_user_message_processing(app: Arc<Application>, sub: Subscription<TelegramUserId, String>) {
+#[instrument(skip(app, sub), fields(chat_id = sub.get_key()))]
+async fn start_user_message_processing(app: Arc<Application>, sub: Subscription<TelegramChatId, UserEvent>) {
     if let Err(err) = user_message_processing_loop(app, sub).await {
         error!("User message processing error: {:?}", err);
     }
 }
 
+/// Передаем событие в обработчик чата, создавая его при необходимости.
+/// В личных чатах это обработчик конкретного пользователя, в группах - общий на всю группу.
+#[instrument(skip(app))]
+async fn send_user_event(app: Arc<Application>, chat_id: TelegramChatId, event: UserEvent){
+    // Получаем канал отправки сообщений для конкретного чата
+    let sender = app
+        .active_processors
+        .subscribe_if_does_not_exist(chat_id, 30, |sub|{
+            tokio::spawn(start_user_message_processing(app.clone(), sub));
+        });
+
+    // Отдаем событие
+    sender
+        .send(event)
+        .await
+        .ok();
+}
+
+/// Группа стала супергруппой: переносим подключенный аккаунт на новый идентификатор чата
+#[instrument(skip(app))]
+async fn process_chat_migration(app: Arc<Application>, old_chat_id: TelegramChatId, new_chat_id: TelegramChatId){
+    if let Err(err) = app.redis_client.move_user_state(old_chat_id, new_chat_id).await {
+        error!("Chat state migration error: {:?}", err);
+    }
+}
+
 #[instrument(skip(app))]
 async fn process_telegram_message(app: Arc<Application>, message: TelegramMessageData){
-    if let (Some(from), Some(text)) = (message.from, message.text){
-        // Получаем канал отправки сообщений для конкретного пользователя
-        let sender = app
-            .active_processors
-            .subscribe_if_does_not_exist(from.id, 30, |sub|{
-                tokio::spawn(start_user_message_processing(app.clone(), sub));
-            });
+    if let Some(new_chat_id) = message.migrate_to_chat_id {
+        process_chat_migration(app, message.chat.id, new_chat_id).await;
+        return;
+    }
+
+    // Пока что обрабатываем только сообщения от пользователей с текстом, подписью или файлом,
+    // а также пересланные посты из каналов, даже без текста у них есть ссылка на сам пост
+    let has_content = message.text.is_some() || 
+                      message.caption.is_some() || 
+                      message.document.is_some() ||
+                      message.forwarded_channel_post().is_some();
+    if message.from.is_none() || !has_content {
+        return;
+    }
+    send_user_event(app, message.chat.id, UserEvent::Message(message)).await;
+}
+
+#[instrument(skip(app))]
+async fn process_telegram_callback_query(app: Arc<Application>, callback_query: TelegramCallbackQueryData){
+    // Кнопка относится к чату, в котором находится сообщение
+    let chat_id = callback_query
+        .message
+        .as_ref()
+        .map(|message| message.chat.id)
+        .unwrap_or(callback_query.from.id);
+    send_user_event(app, chat_id, UserEvent::CallbackQuery(callback_query)).await;
+}
 
-        // Отдаем сообщение
-        sender
-            .send(text)
-            .await
-            .ok();
+/// Обработка одного обновления, общая для long polling и webhook
+#[instrument(skip(app))]
+pub async fn process_telegram_update(app: Arc<Application>, update: TelegramUpdateData){
+    debug!("Received update: {:#?}", update);
+
+    match update.kind {
+        TelegramUpdateKind::Message(message) => {
+            process_telegram_message(app, message).await;
+        },
+        TelegramUpdateKind::CallbackQuery(callback_query) => {
+            process_telegram_callback_query(app, callback_query).await;
+        },
+        TelegramUpdateKind::InlineQuery(query) => {
+            // Не задерживаем получение остальных обновлений
+            tokio::spawn(process_inline_query(app, query));
+        },
+        TelegramUpdateKind::ChosenInlineResult(result) => {
+            process_chosen_inline_result(app, result).await;
+        },
+        TelegramUpdateKind::EditedMessage(message) | TelegramUpdateKind::EditedChannelPost(message) => {
+            // Ссылки сохраняются из исходного сообщения, правки повторно не сохраняем
+            debug!("Skip edited message {} in chat {}", message.message_id, message.chat.id);
+        },
+        TelegramUpdateKind::ChannelPost(message) => {
+            // Посты каналов сохраняются, когда их пересылают боту
+            debug!("Skip channel post {} in chat {}", message.message_id, message.chat.id);
+        },
+        TelegramUpdateKind::MyChatMember(update) => {
+            debug!("Bot status in chat {} changed: {} -> {}", 
+                   update.chat.id, update.old_chat_member.status, update.new_chat_member.status);
+        },
+        TelegramUpdateKind::MessageReaction(reaction) => {
+            debug!("Skip reaction to message {} in chat {}", reaction.message_id, reaction.chat.id);
+        },
+        other @ TelegramUpdateKind::Unknown(_) => {
+            debug!("Skip not supported update: {}", other.name());
+        }
     }
 }
 
 #[instrument(skip(app))]
 pub async fn telegram_receive_updates_loop(app: Arc<Application>) -> Result<(), TelegramBotError>{
-    let mut last_update_id = 0;
+    // Продолжаем с последнего подтвержденного обновления, чтобы после перезапуска
+    // не обработать повторно уже отданные сообщения
+    let mut last_update_id = app
+        .redis_client
+        .get_updates_offset()
+        .await?;
     loop {
         let updates = app
             .telegram_client
-            .get_updates(last_update_id)
+            .get_updates(last_update_id, TELEGRAM_ALLOWED_UPDATES)
             .await?;
 
-        for update in updates.result.into_iter(){
-            debug!("Received update: {:#?}", update);
-            last_update_id = last_update_id.max(update.update_id + 1);
+        for update in updates.into_iter(){
+            let next_update_id = update.update_id + 1;
+            process_telegram_update(app.clone(), update).await;
 
-            if let Some(message) = update.message{
-                process_telegram_message(app.clone(), message).await;
+            // Сдвигаем offset только после того, как обновление передано обработчику
+            if next_update_id > last_update_id {
+                last_update_id = next_update_id;
+                app
+                    .redis_client
+                    .set_updates_offset(last_update_id)
+                    .await?;
             }
         }
     }
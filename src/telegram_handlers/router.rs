@@ -0,0 +1,324 @@
+use futures::{
+    future::{
+        BoxFuture
+    }
+};
+use tracing::{
+    instrument,
+    debug,
+    error
+};
+use tap::{
+    prelude::{
+        *
+    }
+};
+use crate::{
+    app::{
+        Application
+    },
+    error::{
+        TelegramBotError
+    },
+    telegram_client::{
+        TelegramChatId,
+        TelegramUserId,
+        TelegramMessageData
+    },
+    model::{
+        UserState,
+        UserStateKind
+    }
+};
+use super::{
+    commands::{
+        BotCommand,
+        parse_command,
+        find_command_info
+    },
+    user_message::{
+        start_authorization,
+        already_authorized,
+        logout,
+        use_auth_link,
+        save_message_links,
+        save_group_message_links,
+        save_replied_links,
+        show_dashboard,
+        show_help,
+        show_items_list,
+        search_items,
+        archive_item,
+        readd_item,
+        favorite_item,
+        unfavorite_item,
+        delete_item,
+        command_is_not_supported
+    }
+};
+
+////////////////////////////////////////////////////////////////////////
+
+/// Все, что нужно обработчику команды или текста
+pub struct CommandContext<'a> {
+    pub app: &'a Application,
+    pub chat_id: TelegramChatId,
+    pub state: UserState,
+    pub message: TelegramMessageData,
+    /// Текст после имени команды, для обычного текста пустой
+    pub args: String
+}
+
+impl<'a> CommandContext<'a> {
+    /// Токен есть только в авторизованном состоянии, обработчики для него регистрируются только там
+    pub fn pocket_api_token(&self) -> Result<&str, TelegramBotError> {
+        match &self.state {
+            UserState::Authorized{pocket_api_token} => Ok(pocket_api_token),
+            _ => Err(TelegramBotError::NotAuthorized)
+        }
+    }
+}
+
+pub type CommandHandler = for<'c> fn(&'c CommandContext<'c>) -> BoxFuture<'c, Result<(), TelegramBotError>>;
+
+/// Обработчик команды в указанных состояниях.
+/// Одна команда может иметь разные обработчики в разных состояниях.
+pub struct CommandRoute {
+    pub command: BotCommand,
+    pub states: &'static [UserStateKind],
+    /// В группе команда меняет аккаунт или общий список, поэтому доступна только администраторам
+    pub group_admin_only: bool,
+    pub handler: CommandHandler
+}
+
+/// Что делать в состоянии, если команды нет или она там недоступна
+pub struct StateFallbacks {
+    pub state: UserStateKind,
+    /// Обычный текст без команды
+    pub text: CommandHandler,
+    /// Обычный текст без команды в группе, без обработчика такие сообщения пропускаем
+    pub group_text: Option<CommandHandler>,
+    /// Неизвестная или недоступная в этом состоянии команда
+    pub unknown_command: CommandHandler
+}
+
+pub const COMMAND_ROUTES: &[CommandRoute] = &[
+    CommandRoute{
+        command: BotCommand::Start,
+        states: &[UserStateKind::Unauthorized],
+        group_admin_only: true,
+        handler: start_authorization
+    },
+    CommandRoute{
+        command: BotCommand::Start,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: false,
+        handler: already_authorized
+    },
+    CommandRoute{
+        command: BotCommand::Stop,
+        states: &[UserStateKind::AutorizationConfirmationWaiting, UserStateKind::Authorized],
+        group_admin_only: true,
+        handler: logout
+    },
+    CommandRoute{
+        command: BotCommand::Save,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: false,
+        handler: save_replied_links
+    },
+    CommandRoute{
+        command: BotCommand::Dashboard,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: false,
+        handler: show_dashboard
+    },
+    CommandRoute{
+        command: BotCommand::List,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: false,
+        handler: show_items_list
+    },
+    CommandRoute{
+        command: BotCommand::Search,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: false,
+        handler: search_items
+    },
+    CommandRoute{
+        command: BotCommand::Archive,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: true,
+        handler: archive_item
+    },
+    CommandRoute{
+        command: BotCommand::Readd,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: true,
+        handler: readd_item
+    },
+    CommandRoute{
+        command: BotCommand::Favorite,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: true,
+        handler: favorite_item
+    },
+    CommandRoute{
+        command: BotCommand::Unfavorite,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: true,
+        handler: unfavorite_item
+    },
+    CommandRoute{
+        command: BotCommand::Delete,
+        states: &[UserStateKind::Authorized],
+        group_admin_only: true,
+        handler: delete_item
+    },
+    CommandRoute{
+        command: BotCommand::Help,
+        states: &[UserStateKind::Unauthorized, UserStateKind::AutorizationConfirmationWaiting, UserStateKind::Authorized],
+        group_admin_only: false,
+        handler: show_help
+    }
+];
+
+const STATE_FALLBACKS: &[StateFallbacks] = &[
+    StateFallbacks{
+        state: UserStateKind::Unauthorized,
+        text: show_help,
+        group_text: None,
+        unknown_command: command_is_not_supported
+    },
+    StateFallbacks{
+        state: UserStateKind::AutorizationConfirmationWaiting,
+        text: use_auth_link,
+        group_text: None,
+        unknown_command: use_auth_link
+    },
+    StateFallbacks{
+        state: UserStateKind::Authorized,
+        text: save_message_links,
+        group_text: Some(save_group_message_links),
+        unknown_command: command_is_not_supported
+    }
+];
+
+////////////////////////////////////////////////////////////////////////
+
+/// Обработчик команды для состояния
+pub fn find_route(command: BotCommand, state: UserStateKind) -> Option<&'static CommandRoute> {
+    COMMAND_ROUTES
+        .iter()
+        .find(|route| route.command == command && route.states.contains(&state))
+}
+
+fn find_fallbacks(state: UserStateKind) -> &'static StateFallbacks {
+    STATE_FALLBACKS
+        .iter()
+        .find(|fallbacks| fallbacks.state == state)
+        .expect("Fallbacks are not registered for state")
+}
+
+/// Подключать аккаунт и менять общий список в группе могут только администраторы,
+/// в личном чате ограничений нет
+#[instrument(skip(app))]
+pub async fn can_manage_chat(app: &Application,
+                             chat_id: TelegramChatId,
+                             is_private: bool,
+                             user_id: Option<TelegramUserId>) -> Result<bool, TelegramBotError> {
+    if is_private {
+        return Ok(true);
+    }
+    let user_id = match user_id {
+        Some(user_id) => user_id,
+        None => return Ok(false)
+    };
+    let member = app
+        .telegram_client
+        .get_chat_member(chat_id, user_id)
+        .await
+        .tap_err(|e|{ error!("Get chat member error: {}", e) })?;
+    debug!("Chat member status: {}", member.status);
+    Ok(member.is_admin())
+}
+
+/// Выбор обработчика для сообщения по команде и текущему состоянию чата
+#[instrument(skip(app, message))]
+pub async fn route_message(app: &Application, chat_id: TelegramChatId, message: TelegramMessageData) -> Result<(), TelegramBotError> {
+    // Получаем текущее состояние пользователя
+    let state = app
+        .redis_client
+        .get_user_state(chat_id)
+        .await
+        .tap_err(|e|{ error!("Get user state error: {}", e) })?;
+    debug!("User state: {:?}", state);
+
+    let state_kind = state.kind();
+    let fallbacks = find_fallbacks(state_kind);
+    let is_private = message.chat.is_private();
+
+    let parsed = message
+        .text
+        .as_deref()
+        .and_then(parse_command);
+    let (handler, args) = match parsed {
+        Some(parsed) => {
+            // Команда адресована другому боту
+            if let Some(mention) = parsed.mention {
+                if !app.telegram_bot.is_mentioned(mention) {
+                    debug!("Skip command for other bot: {}", mention);
+                    return Ok(());
+                }
+            }
+
+            // В группе неизвестная команда без упоминания может быть для другого бота
+            let info = find_command_info(parsed.name);
+            if info.is_none() && !is_private && parsed.mention.is_none() {
+                debug!("Skip unknown group command: {}", parsed.name);
+                return Ok(());
+            }
+
+            let route = info.and_then(|info| find_route(info.command, state_kind));
+            debug!("Command: {}, route found: {}", parsed.name, route.is_some());
+
+            if matches!(route, Some(route) if route.group_admin_only) {
+                let user_id = message
+                    .from
+                    .as_ref()
+                    .map(|user| user.id);
+                if !can_manage_chat(app, chat_id, is_private, user_id).await? {
+                    app
+                        .telegram_client
+                        .message_handle(chat_id, message.message_id)
+                        .reply(format!("Only chat administrators can use /{}", parsed.name))
+                        .await
+                        .tap_err(|e|{ error!("Message send error: {}", e) })?;
+                    return Ok(());
+                }
+            }
+            let handler = route
+                .map(|route| route.handler)
+                .unwrap_or(fallbacks.unknown_command);
+            (handler, parsed.args.to_string())
+        },
+        // В группах обычную переписку пропускаем, кроме того, что состояние разрешает обрабатывать
+        None if !is_private => match fallbacks.group_text {
+            Some(handler) => (handler, String::new()),
+            None => {
+                debug!("Skip group message without command");
+                return Ok(());
+            }
+        },
+        None => (fallbacks.text, String::new())
+    };
+
+    let context = CommandContext{
+        app,
+        chat_id,
+        state,
+        message,
+        args
+    };
+    handler(&context).await
+}
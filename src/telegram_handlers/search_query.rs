@@ -0,0 +1,71 @@
+use crate::{
+    pocket_items::{
+        PocketGetQuery,
+        PocketItemState
+    }
+};
+
+/// Синтаксис аргументов `/search` для справки
+pub const SEARCH_USAGE: &str = "<text> [tag:<tag>] [domain:<domain>] [state:archived|unread] [favorite]";
+
+/// Разобранный запрос `/search`: текст для поиска по заголовку и адресу плюс фильтры
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub text: Option<String>,
+    pub tag: Option<String>,
+    pub domain: Option<String>,
+    /// По-умолчанию ищем и в непрочитанном, и в архиве
+    pub state: Option<PocketItemState>,
+    pub favorite: bool
+}
+
+impl SearchQuery {
+    /// Разбор аргументов команды, при ошибке возвращается текст для пользователя
+    pub fn parse(args: &str) -> Result<SearchQuery, String> {
+        let mut query = SearchQuery::default();
+        let mut words: Vec<&str> = Vec::new();
+
+        for token in args.split_whitespace() {
+            if let Some(tag) = token.strip_prefix("tag:") {
+                query.tag = Some(tag.to_string()).filter(|tag| !tag.is_empty());
+            } else if let Some(domain) = token.strip_prefix("domain:") {
+                query.domain = Some(domain.to_lowercase()).filter(|domain| !domain.is_empty());
+            } else if let Some(state) = token.strip_prefix("state:") {
+                query.state = match state.to_lowercase().as_str() {
+                    "archived" | "archive" => Some(PocketItemState::Archive),
+                    "unread" => Some(PocketItemState::Unread),
+                    "all" => Some(PocketItemState::All),
+                    _ => return Err(format!("Unknown state: {}, use state:archived or state:unread", state))
+                };
+            } else if token.eq_ignore_ascii_case("favorite") {
+                query.favorite = true;
+            } else {
+                words.push(token);
+            }
+        }
+
+        if !words.is_empty() {
+            query.text = Some(words.join(" "));
+        }
+
+        let has_filters = query.tag.is_some() || query.domain.is_some() || query.state.is_some() || query.favorite;
+        if query.text.is_none() && !has_filters {
+            return Err(format!("Usage: /search {}", SEARCH_USAGE));
+        }
+
+        Ok(query)
+    }
+
+    /// Параметры выборки для страницы результатов
+    pub fn to_get_query(&self, count: usize, offset: usize) -> PocketGetQuery {
+        PocketGetQuery{
+            search: self.text.clone(),
+            state: Some(self.state.unwrap_or(PocketItemState::All)),
+            favorite: if self.favorite { Some(1) } else { None },
+            tag: self.tag.clone(),
+            domain: self.domain.clone(),
+            count: Some(count),
+            offset: Some(offset)
+        }
+    }
+}
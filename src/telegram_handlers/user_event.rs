@@ -0,0 +1,16 @@
+use crate::{
+    telegram_client::{
+        TelegramMessageData,
+        TelegramCallbackQueryData
+    }
+};
+
+/// Событие от пользователя, которое передается в его персональный обработчик
+#[derive(Debug)]
+pub enum UserEvent{
+    /// Сообщение, у которого есть текст, подпись или файл
+    Message(TelegramMessageData),
+
+    /// Нажатие на inline кнопку
+    CallbackQuery(TelegramCallbackQueryData)
+}
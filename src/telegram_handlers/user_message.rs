@@ -11,9 +11,16 @@ use tokio::{
         timeout
     }
 };
+use futures::{
+    future::{
+        BoxFuture,
+        FutureExt
+    }
+};
 use tracing::{
     instrument,
     debug,
+    warn,
     error
 };
 use tap::{
@@ -32,192 +39,708 @@ use crate::{
         TelegramBotError
     },
     telegram_client::{
-        TelegramClient,
-        TelegramUserId
+        TelegramChatId,
+        TelegramMessageId,
+        TelegramMessageData,
+        TelegramFormattedText,
+        TelegramOutgoingText,
+        TelegramParseMode,
+        TelegramChatAction,
+        TelegramLinkPreviewOptions,
+        TelegramReplyMarkup,
+        TelegramReplyKeyboardMarkup,
+        TelegramKeyboardButton,
+        TelegramReplyKeyboardRemove,
+        TelegramReactionTypeData
+    },
+    pocket_items::{
+        PocketItem
     },
     model::{
-        UserState
+        UserState,
+        SearchSession,
+        SavedItem
+    }
+};
+use super::{
+    user_event::{
+        UserEvent
+    },
+    commands::{
+        help_text
+    },
+    callback_query::{
+        process_callback_query
+    },
+    item_actions::{
+        ItemAction,
+        saved_item_buttons,
+        apply_item_action_command
+    },
+    item_list::{
+        ItemsSource,
+        send_items_list
+    },
+    search_query::{
+        SearchQuery
+    },
+    router::{
+        CommandContext,
+        route_message
+    },
+    file_import::{
+        import_links_from_document
+    },
+    dashboard::{
+        create_dashboard,
+        refresh_dashboard,
+        update_stats
     }
 };
 
-#[instrument(skip(client), fields(user_id))]
-async fn send_command_is_not_supported(client: &TelegramClient, user_id: TelegramUserId) -> Result<(), TelegramBotError> {
-    let msg = client
-        .send_message(user_id, "Command is not supported".to_string())
-        .await?;
-    debug!("Message send result: {:#?}", msg.get_data());
-    Ok(())
+/// Подтверждение сохранения: заголовок ссылкой, сайт и время чтения
+fn saved_item_text(item: &PocketItem, link: &str) -> TelegramOutgoingText {
+    let url = item.url().unwrap_or(link);
+    let title = item.title().unwrap_or(url);
+
+    let mut text = TelegramFormattedText::new(TelegramParseMode::Html)
+        .bold("Saved: ")
+        .link(title, url);
+    if let Some(summary) = item.summary() {
+        text = text
+            .new_line()
+            .italic(summary);
+    }
+
+    TelegramOutgoingText::from(text)
+        .without_link_preview()
 }
 
-#[instrument(skip(app))]
-async fn process_unautorized(app: &Application, user_id: TelegramUserId, msg: String) -> Result<(), TelegramBotError> {
-    match msg.as_str() {
-        "/start" => {
-            // Инфа по аутентификации
-            let auth_info = app
-                .pocket_token_receiver
-                .optain_user_auth_info(&[
-                    ("user_id", &format!("{}", user_id))
-                ])
-                .await
-                .tap_err(|e|{ error!("User auth error: {}", e) })?;
+/// Ссылки сообщения и теги для них. У поста, пересланного из канала,
+/// добавляется ссылка на сам пост и тег с именем канала.
+fn message_links_with_tags(message: &TelegramMessageData) -> (Vec<String>, Vec<String>) {
+    let mut links = message.extract_links();
+    let mut tags = Vec::new();
+    if let Some(post) = message.forwarded_channel_post() {
+        if !links.contains(&post.link) {
+            links.push(post.link);
+        }
+        // Запятая в Pocket разделяет теги
+        tags.push(post.channel_name.replace(',', " "));
+    }
+    (links, tags)
+}
 
-            // Пишем сообщение с ссылкой на подтверждение прав доступа
-            let message = app
-                .telegram_client
-                .send_message(user_id, auth_info.auth_url.to_string())
-                .await
-                .tap_err(|e|{ error!("Message send error: {}", e) })?;
+/// Сохранение ссылок с подтверждением ответом на исходное сообщение,
+/// в группе так видно, какая именно ссылка сохранена
+#[instrument(skip(app, pocket_api_token))]
+async fn save_links(app: &Application,
+                    pocket_api_token: &str,
+                    chat_id: TelegramChatId,
+                    source_message_id: TelegramMessageId,
+                    links: Vec<String>,
+                    tags: &[String]) -> Result<(), TelegramBotError> {
+    let mut confirmation_ids = Vec::new();
+    let mut saved_any = false;
+    for link in links {
+        // Добавляем данному клиенту новое сообщение
+        let result = app
+            .telegram_client
+            .with_chat_action(chat_id, TelegramChatAction::Typing, app
+                .pocket_items
+                .add(pocket_api_token, &link, tags))
+            .await
+            .tap_err(|e|{ error!("Pocket url append error: {}", e) });
+
+        // Неудачную ссылку запоминаем для статуса и переходим к следующей
+        let item = match result {
+            Ok(item) => item,
+            Err(_) => {
+                update_stats(app, chat_id, |stats| stats.record_failed(&link)).await?;
+                app
+                    .telegram_client
+                    .message_handle(chat_id, source_message_id)
+                    .reply(format!("Failed to save {}", link))
+                    .await
+                    .tap_err(|e|{ error!("Message send error: {}", e) })?;
+                continue;
+            }
+        };
+        update_stats(app, chat_id, |stats| stats.record_saved(&link, item.title())).await?;
+        saved_any = true;
 
-            // Обновляем состояние
+        // Сообщение пользователю, кнопки действий только если Pocket вернул идентификатор
+        let saved_item = item
+            .item_id
+            .clone()
+            .map(|item_id| SavedItem{
+                item_id,
+                archived: false,
+                favorite: false
+            });
+        let text = match saved_item.as_ref() {
+            Some(saved_item) => saved_item_text(&item, &link).with_reply_markup(saved_item_buttons(saved_item)),
+            None => saved_item_text(&item, &link)
+        };
+        let message = app
+            .telegram_client
+            .message_handle(chat_id, source_message_id)
+            .reply(text)
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+
+        if let Some(saved_item) = saved_item {
             app
                 .redis_client
-                .set_user_state(user_id, UserState::AutorizationConfirmationWaiting{
-                    telegram_message_id: message.message_id,
-                    telegram_user_id: user_id,
-                    pocket_auth_code: auth_info.code,
-                    pocket_auth_url: auth_info.auth_url.to_string()
-                }, Some(Duration::from_secs(60 * 10)))
+                .set_saved_item(chat_id, message.message_id, Some(&saved_item))
                 .await
-                .tap_err(|e|{ error!("Update send error: {}", e) })?;
-        },
-        _ => {
-            send_command_is_not_supported(&app.telegram_client, user_id)
-                .await
-                .tap_err(|e|{ error!("Command is not supported error: {}", e) })?;
+                .tap_err(|e|{ error!("Set saved item error: {}", e) })?;
+            confirmation_ids.push(message.message_id);
+        }
+    }
+
+    // Команды действий без ответа на сообщение применяются к последнему сохранению
+    if !confirmation_ids.is_empty() {
+        app
+            .redis_client
+            .set_last_saved_messages(chat_id, &confirmation_ids)
+            .await
+            .tap_err(|e|{ error!("Set last saved messages error: {}", e) })?;
+    }
+
+    // Отметка на исходном сообщении, в чате реакции могут быть запрещены, это не ошибка сохранения
+    if saved_any {
+        let reaction = app
+            .telegram_client
+            .message_handle(chat_id, source_message_id)
+            .react(Some(TelegramReactionTypeData::emoji("👍")))
+            .await;
+        if let Err(err) = reaction {
+            warn!("Set reaction error: {}", err);
         }
     }
     Ok(())
 }
 
-#[instrument(skip(app))]
-async fn process_confirmation_waiting(app: &Application, user_id: TelegramUserId, msg: String) -> Result<(), TelegramBotError> {
-    // Пишем сообщение с ссылкой на подтверждение прав доступа
-    match msg.as_str() {
-        "/stop" => {
-            // Обновляем состояние
-            app
-                .redis_client
-                .set_user_state(user_id, UserState::Unauthorized, Some(Duration::from_secs(60 * 10)))
-                .await
-                .tap_err(|e|{ error!("Update send error: {}", e) })?;   
-            
-            // Сообщение
-            app
-                .telegram_client
-                .send_message(user_id, "Logout success".to_string())
-                .await
-                .tap_err(|e|{ error!("Message send error: {}", e) })?;                            
+////////////////////////////////////////////////////////////////////////
+
+/// Начало авторизации: ссылка на подтверждение доступа к Pocket
+pub fn start_authorization<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let (app, chat_id) = (ctx.app, ctx.chat_id);
+
+        // Инфа по аутентификации
+        let chat_id_param = format!("{}", chat_id);
+        let auth_info = app
+            .telegram_client
+            .with_chat_action(chat_id, TelegramChatAction::Typing, app
+                .pocket_token_receiver
+                .optain_user_auth_info(&[
+                    ("chat_id", &chat_id_param)
+                ]))
+            .await
+            .tap_err(|e|{ error!("User auth error: {}", e) })?;
+
+        // Пишем сообщение с ссылкой на подтверждение прав доступа
+        let message = app
+            .telegram_client
+            .send_message(chat_id, auth_info.auth_url.to_string())
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+
+        // Обновляем состояние
+        app
+            .redis_client
+            .set_user_state(chat_id, UserState::AutorizationConfirmationWaiting{
+                telegram_message_id: message.message_id,
+                telegram_chat_id: chat_id,
+                pocket_auth_code: auth_info.code,
+                pocket_auth_url: auth_info.auth_url.to_string()
+            }, Some(Duration::from_secs(60 * 10)))
+            .await
+            .tap_err(|e|{ error!("Update send error: {}", e) })?;
+        refresh_dashboard(app, chat_id).await;
+
+        Ok(())
+    }.boxed()
+}
+
+pub fn already_authorized<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        // Убираем клавиатуру с /start, оставшуюся после выхода
+        ctx.app
+            .telegram_client
+            .send_message_with_markup(ctx.chat_id, 
+                                      "Already authorized".to_string(), 
+                                      Some(TelegramReplyKeyboardRemove::default().into()))
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+        Ok(())
+    }.boxed()
+}
+
+/// Отключение аккаунта, работает и во время ожидания подтверждения
+pub fn logout<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let (app, chat_id) = (ctx.app, ctx.chat_id);
+
+        // Обновляем состояние
+        app
+            .redis_client
+            .set_user_state(chat_id, UserState::Unauthorized, Some(Duration::from_secs(60 * 10)))
+            .await
+            .tap_err(|e|{ error!("Update send error: {}", e) })?;
+        refresh_dashboard(app, chat_id).await;
+
+        // Сообщение, в личном чате с кнопкой для повторного входа
+        let keyboard = if ctx.message.chat.is_private() {
+            let keyboard = TelegramReplyKeyboardMarkup{
+                one_time_keyboard: Some(true),
+                ..TelegramReplyKeyboardMarkup::new(vec![vec![TelegramKeyboardButton::new("/start")]])
+            };
+            Some(TelegramReplyMarkup::from(keyboard))
+        }else{
+            None
+        };
+        app
+            .telegram_client
+            .send_message_with_markup(chat_id, "Logout success".to_string(), keyboard)
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+        Ok(())
+    }.boxed()
+}
+
+pub fn use_auth_link<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        ctx.app
+            .telegram_client
+            .send_message(ctx.chat_id, "Use auth link above".to_string())
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+        Ok(())
+    }.boxed()
+}
+
+pub fn command_is_not_supported<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let msg = ctx.app
+            .telegram_client
+            .send_message(ctx.chat_id, "Command is not supported, see /help".to_string())
+            .await
+            .tap_err(|e|{ error!("Command is not supported error: {}", e) })?;
+        debug!("Message send result: {:#?}", msg.get_data());
+        Ok(())
+    }.boxed()
+}
+
+/// Обычное сообщение: ссылки из него, из пересланного поста или из присланного файла
+pub fn save_message_links<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let (app, chat_id, message) = (ctx.app, ctx.chat_id, &ctx.message);
+        let pocket_api_token = ctx.pocket_api_token()?;
+
+        // Файл со списком ссылок или экспортом закладок
+        if let Some(document) = message.document.as_ref() {
+            return import_links_from_document(app, pocket_api_token, chat_id, document).await;
         }
-        _ => {
+
+        // Ссылки могут быть в тексте, скрыты за гиперссылками, быть в подписи к медиа
+        // или это может быть пересланный пост из канала
+        let (links, tags) = message_links_with_tags(message);
+        if links.is_empty() {
             app
                 .telegram_client
-                .send_message(user_id, "Use auth link above".to_string())
+                .send_message(chat_id, "This is not url".to_string())
                 .await
                 .tap_err(|e|{ error!("Message send error: {}", e) })?;
+            return Ok(());
         }
-    }
 
-    Ok(())
+        save_links(app, pocket_api_token, chat_id, message.message_id, links, &tags).await
+    }.boxed()
+}
+
+/// Ссылки из обычных сообщений группы, чтобы любой участник мог пополнять общий список.
+/// Сообщения без ссылок - обычная переписка, на них не отвечаем.
+pub fn save_group_message_links<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let (app, chat_id, message) = (ctx.app, ctx.chat_id, &ctx.message);
+        let pocket_api_token = ctx.pocket_api_token()?;
+
+        let (links, tags) = message_links_with_tags(message);
+        if links.is_empty() {
+            debug!("Skip group message without links");
+            return Ok(());
+        }
+
+        save_links(app, pocket_api_token, chat_id, message.message_id, links, &tags).await
+    }.boxed()
 }
 
-#[instrument(skip(app))]
-async fn process_autorized(app: &Application, pocket_api_token: String, user_id: TelegramUserId, msg: String) -> Result<(), TelegramBotError> {
-    match msg.as_str() {
-        "/start" => {
+/// `/save` ответом на сообщение со ссылкой, ссылки можно передать и аргументами
+pub fn save_replied_links<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let (app, chat_id, message) = (ctx.app, ctx.chat_id, &ctx.message);
+        let pocket_api_token = ctx.pocket_api_token()?;
+
+        // Ссылки из сообщения, на которое ответили, и из самой команды
+        let source = message
+            .reply_to_message
+            .as_deref()
+            .unwrap_or(message);
+        let (mut links, tags) = message_links_with_tags(source);
+        if source.message_id != message.message_id {
+            links.extend(message
+                .extract_links()
+                .into_iter()
+                .filter(|link| !links.contains(link))
+                .collect::<Vec<String>>());
+        }
+        if links.is_empty() {
             app
                 .telegram_client
-                .send_message(user_id, "Already authorized".to_string())
+                .message_handle(chat_id, message.message_id)
+                .reply("Reply with /save to a message with a link".to_string())
                 .await
                 .tap_err(|e|{ error!("Message send error: {}", e) })?;
-        },
-        "/stop" => {
-            // Обновляем состояние
-            app
-                .redis_client
-                .set_user_state(user_id, UserState::Unauthorized, Some(Duration::from_secs(60 * 10)))
-                .await
-                .tap_err(|e|{ error!("Update send error: {}", e) })?;   
-            
-            // Сообщение
-            app
-                .telegram_client
-                .send_message(user_id, "Logout success".to_string())
-                .await
-                .tap_err(|e|{ error!("Message send error: {}", e) })?;                            
+            return Ok(());
         }
-        text => {
-            let is_url = validator::validate_url(text);
-            if is_url {
-                // Добавляем данному клиенту новое сообщение
-                let pocket_res = {
-                    let client = pocket_api_client::PocketApiClient::new(app.pocket_api_config.clone(), 
-                                                                        pocket_api_token);
-                    client
-                        .add(msg, None)
-                        .await
-                        .tap_err(|e|{ error!("Pocket url append error: {}", e) })?
-                };
-
-                // Сообщение пользователю
-                app
-                    .telegram_client
-                    .send_message(user_id, format!("Url: {}", pocket_res.normal_url))
-                    .await
-                    .tap_err(|e|{ error!("Message send error: {}", e) })?;
-            }else{
+
+        save_links(app, pocket_api_token, chat_id, source.message_id, links, &tags).await
+    }.boxed()
+}
+
+pub fn show_dashboard<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        create_dashboard(ctx.app, ctx.chat_id)
+            .await
+            .tap_err(|e|{ error!("Dashboard create error: {}", e) })
+    }.boxed()
+}
+
+pub fn show_help<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        ctx.app
+            .telegram_client
+            .send_text_chunked(ctx.chat_id, help_text(ctx.state.kind()), Some(TelegramLinkPreviewOptions::disabled()), None)
+            .await
+            .tap_err(|e|{ error!("Message send error: {}", e) })?;
+        Ok(())
+    }.boxed()
+}
+
+pub fn show_items_list<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let pocket_api_token = ctx.pocket_api_token()?;
+        send_items_list(ctx.app, pocket_api_token, ctx.chat_id, &ItemsSource::Unread).await?;
+        Ok(())
+    }.boxed()
+}
+
+/// Поиск по сохраненному, запрос запоминается для перелистывания страниц
+pub fn search_items<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let (app, chat_id) = (ctx.app, ctx.chat_id);
+        let pocket_api_token = ctx.pocket_api_token()?;
+
+        let query = match SearchQuery::parse(&ctx.args) {
+            Ok(query) => query,
+            Err(text) => {
                 app
                     .telegram_client
-                    .send_message(user_id, "This is not url".to_string())
+                    .send_message(chat_id, text)
                     .await
                     .tap_err(|e|{ error!("Message send error: {}", e) })?;
+                return Ok(());
             }
-        }
-    }
-    Ok(())
+        };
+        debug!("Search query: {:?}", query);
+
+        let message_id = send_items_list(app, pocket_api_token, chat_id, &ItemsSource::Search(&query)).await?;
+        app
+            .redis_client
+            .set_search_session(chat_id, message_id, &SearchSession{
+                args: ctx.args.clone(),
+                page: 0
+            })
+            .await
+            .tap_err(|e|{ error!("Set search session error: {}", e) })?;
+
+        Ok(())
+    }.boxed()
+}
+
+/// Команда действия с элементом: ответом на подтверждение сохранения
+/// или для ссылок из последнего сохранения
+fn item_action_command<'a>(ctx: &'a CommandContext<'a>, action: ItemAction) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    async move {
+        let pocket_api_token = ctx.pocket_api_token()?;
+        let reply_to_message_id = ctx
+            .message
+            .reply_to_message
+            .as_ref()
+            .map(|message| message.message_id);
+        apply_item_action_command(ctx.app, pocket_api_token, ctx.chat_id, reply_to_message_id, action).await
+    }.boxed()
+}
+
+pub fn archive_item<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    item_action_command(ctx, ItemAction::Archive)
+}
+
+pub fn readd_item<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    item_action_command(ctx, ItemAction::Readd)
 }
 
-/// Данная функция занимается обработкой сообщений от конкретного пользователя
-/// Живет ограниченное количество времени до тех пор, пока приходят периодически сообщения от пользователя
-#[instrument(skip(app, sub), fields(user_id = sub.get_key()))]
+pub fn favorite_item<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    item_action_command(ctx, ItemAction::Favorite)
+}
+
+pub fn unfavorite_item<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    item_action_command(ctx, ItemAction::Unfavorite)
+}
+
+pub fn delete_item<'a>(ctx: &'a CommandContext<'a>) -> BoxFuture<'a, Result<(), TelegramBotError>> {
+    item_action_command(ctx, ItemAction::Delete)
+}
+
+/// Данная функция занимается обработкой сообщений из конкретного чата: личного или группы
+/// Живет ограниченное количество времени до тех пор, пока приходят периодически сообщения из чата
+#[instrument(skip(app, sub), fields(chat_id = sub.get_key()))]
 pub async fn user_message_processing_loop(app: Arc<Application>, 
-                                          mut sub: Subscription<TelegramUserId, String>) -> Result<(), TelegramBotError>{
+                                          mut sub: Subscription<TelegramChatId, UserEvent>) -> Result<(), TelegramBotError>{
     // TODO: Сделать машину состояний с сохранением в базу данных состояния?
 
-    let user_id = sub
-        .get_key()
-        .clone();
+    let chat_id = *sub.get_key();
 
     debug!("Processing for {} started", sub.get_key());
-    while let Some(Some(msg)) = timeout(Duration::from_secs(60), sub.recv()).await.ok() {
-        debug!("Message received: {}", msg);
+    while let Ok(Some(event)) = timeout(Duration::from_secs(60), sub.recv()).await {
+        debug!("Event received: {:?}", event);
 
-        // Получаем текущее состояние пользователя
-        let user_state = app
-            .redis_client
-            .get_user_state(user_id)
-            .await
-            .tap_err(|e|{ error!("Get user state error: {}", e) })?;
-        debug!("User state: {:?}", user_state);
-
-        // Обрабатываем в зависимости от состояния
-        match user_state {
-            UserState::Unauthorized => {
-                debug!("User is unauthorized in pocket");
-                process_unautorized(app.as_ref(), user_id, msg).await?;
-            },
-            UserState::AutorizationConfirmationWaiting{..} => {
-                debug!("User confirmation waiting");
-                process_confirmation_waiting(app.as_ref(), user_id, msg).await?;
+        match event {
+            UserEvent::Message(message) => {
+                route_message(app.as_ref(), chat_id, message).await?;
             },
-            UserState::Authorized{pocket_api_token} => {
-                debug!("User is authorized in pocket: {}", pocket_api_token);
-                process_autorized(app.as_ref(), pocket_api_token, user_id, msg)
-                    .await?;
+            UserEvent::CallbackQuery(callback_query) => {
+                process_callback_query(app.as_ref(), chat_id, callback_query).await?;
             }
         }
     }
-    debug!("Processing for {} finished", user_id);
+    debug!("Processing for {} finished", chat_id);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json,
+        Value
+    };
+    use reqwest::{
+        StatusCode
+    };
+    use crate::{
+        telegram_client::{
+            TelegramFakeTransport
+        },
+        pocket_items::{
+            PocketFakeTransport
+        }
+    };
+    use super::*;
+
+    const CHAT_ID: TelegramChatId = 1000;
+    const GROUP_CHAT_ID: TelegramChatId = -1001;
+
+    async fn authorized_app() -> (Application, Arc<TelegramFakeTransport>, Arc<PocketFakeTransport>) {
+        authorized_chat_app(CHAT_ID).await
+    }
+
+    async fn authorized_chat_app(chat_id: TelegramChatId) -> (Application, Arc<TelegramFakeTransport>, Arc<PocketFakeTransport>) {
+        let telegram = Arc::new(TelegramFakeTransport::default());
+        let pocket = Arc::new(PocketFakeTransport::default());
+        let app = Application::with_fakes(telegram.clone(), pocket.clone());
+        app.redis_client
+            .set_user_state(chat_id, UserState::Authorized{
+                pocket_api_token: "token".to_string()
+            }, None)
+            .await
+            .unwrap();
+        (app, telegram, pocket)
+    }
+
+    fn group_message(message_id: TelegramMessageId, text: &str) -> TelegramMessageData {
+        serde_json::from_value(json!({
+            "message_id": message_id,
+            "from": {"id": CHAT_ID, "is_bot": false, "first_name": "User"},
+            "chat": {"id": GROUP_CHAT_ID, "type": "supergroup", "title": "Team"},
+            "text": text
+        })).unwrap()
+    }
+
+    fn chat_member(status: &str) -> Value {
+        json!({
+            "status": status,
+            "user": {"id": CHAT_ID, "is_bot": false, "first_name": "User"}
+        })
+    }
+
+    fn private_message(message_id: TelegramMessageId, text: &str) -> TelegramMessageData {
+        serde_json::from_value(json!({
+            "message_id": message_id,
+            "from": {"id": CHAT_ID, "is_bot": false, "first_name": "User"},
+            "chat": {"id": CHAT_ID, "type": "private"},
+            "text": text
+        })).unwrap()
+    }
+
+    fn sent_message(message_id: TelegramMessageId) -> Value {
+        json!({
+            "message_id": message_id,
+            "chat": {"id": CHAT_ID, "type": "private"},
+            "text": "Saved"
+        })
+    }
+
+    /// Вызовы Telegram без индикатора набора, он отправляется в зависимости от времени
+    fn telegram_calls(telegram: &TelegramFakeTransport) -> Vec<(String, Value)> {
+        telegram
+            .calls()
+            .into_iter()
+            .filter(|(method, _)| method != "sendChatAction")
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_save_link_from_private_message() {
+        let (app, telegram, pocket) = authorized_app().await;
+        pocket.push_result("add", json!({
+            "item": {
+                "item_id": "555",
+                "given_url": "https://example.com/article",
+                "resolved_title": "Example article"
+            }
+        }));
+        telegram.push_result("sendMessage", sent_message(11));
+        telegram.push_result("setMessageReaction", json!(true));
+
+        route_message(&app, CHAT_ID, private_message(10, "https://example.com/article")).await.unwrap();
+
+        assert_eq!(pocket.calls(), vec![
+            ("add".to_string(), json!({"url": "https://example.com/article"}))
+        ]);
+
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 2);
+        let (method, params) = &calls[0];
+        assert_eq!(method, "sendMessage");
+        assert_eq!(params["chat_id"], json!(CHAT_ID));
+        assert_eq!(params["reply_parameters"]["message_id"], json!(10));
+        assert_eq!(params["parse_mode"], json!("HTML"));
+        assert!(params["text"].as_str().unwrap().contains("Example article"));
+        assert_eq!(params["reply_markup"]["inline_keyboard"][0][0]["callback_data"], json!("arc:555"));
+        assert_eq!(calls[1].0, "setMessageReaction");
+        assert_eq!(calls[1].1["message_id"], json!(10));
+
+        // Кнопки подтверждения и команды без ответа работают с сохраненным элементом
+        let saved_item = app.redis_client.get_saved_item(CHAT_ID, 11).await.unwrap().unwrap();
+        assert_eq!(saved_item.item_id, "555");
+        assert_eq!(app.redis_client.get_last_saved_messages(CHAT_ID).await.unwrap(), vec![11]);
+        let stats = app.redis_client.get_user_stats(CHAT_ID).await.unwrap();
+        assert_eq!(stats.saved_today, 1);
+        assert_eq!(stats.last_saved_title.as_deref(), Some("Example article"));
+    }
+
+    #[tokio::test]
+    async fn test_save_link_failed() {
+        let (app, telegram, pocket) = authorized_app().await;
+        pocket.push_error("add", StatusCode::SERVICE_UNAVAILABLE);
+        telegram.push_result("sendMessage", sent_message(11));
+
+        route_message(&app, CHAT_ID, private_message(10, "https://example.com/article")).await.unwrap();
+
+        // Без реакции на исходное сообщение, ничего не сохранено
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1["text"], json!("Failed to save https://example.com/article"));
+        assert_eq!(calls[0].1["reply_parameters"]["message_id"], json!(10));
+
+        let stats = app.redis_client.get_user_stats(CHAT_ID).await.unwrap();
+        assert_eq!(stats.saved_today, 0);
+        assert_eq!(stats.failed_saves, vec!["https://example.com/article".to_string()]);
+        assert!(app.redis_client.get_last_saved_messages(CHAT_ID).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_private_message_without_links() {
+        let (app, telegram, pocket) = authorized_app().await;
+        telegram.push_result("sendMessage", sent_message(11));
+
+        route_message(&app, CHAT_ID, private_message(10, "just text")).await.unwrap();
+
+        assert!(pocket.calls().is_empty());
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1["text"], json!("This is not url"));
+    }
+
+    #[tokio::test]
+    async fn test_group_item_command_requires_admin() {
+        let (app, telegram, pocket) = authorized_chat_app(GROUP_CHAT_ID).await;
+        telegram.push_result("getChatMember", chat_member("member"));
+        telegram.push_result("sendMessage", sent_message(11));
+
+        route_message(&app, GROUP_CHAT_ID, group_message(10, "/delete")).await.unwrap();
+
+        assert!(pocket.calls().is_empty());
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].0, "getChatMember");
+        assert_eq!(calls[0].1, json!({"chat_id": GROUP_CHAT_ID, "user_id": CHAT_ID}));
+        assert_eq!(calls[1].1["chat_id"], json!(GROUP_CHAT_ID));
+        assert_eq!(calls[1].1["text"], json!("Only chat administrators can use /delete"));
+    }
+
+    #[tokio::test]
+    async fn test_group_logout_by_admin() {
+        let (app, telegram, _) = authorized_chat_app(GROUP_CHAT_ID).await;
+        telegram.push_result("getChatMember", chat_member("administrator"));
+        telegram.push_result("sendMessage", sent_message(11));
+
+        route_message(&app, GROUP_CHAT_ID, group_message(10, "/stop")).await.unwrap();
+
+        let calls = telegram_calls(&telegram);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].0, "getChatMember");
+        assert_eq!(calls[1].1["text"], json!("Logout success"));
+        let state = app.redis_client.get_user_state(GROUP_CHAT_ID).await.unwrap();
+        assert!(matches!(state, UserState::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn test_group_links_saved_without_admin_check() {
+        let (app, telegram, pocket) = authorized_chat_app(GROUP_CHAT_ID).await;
+        pocket.push_result("add", json!({
+            "item": {
+                "item_id": "555",
+                "given_url": "https://example.com/article",
+                "resolved_title": "Example article"
+            }
+        }));
+        telegram.push_result("sendMessage", sent_message(11));
+        telegram.push_result("setMessageReaction", json!(true));
+
+        route_message(&app, GROUP_CHAT_ID, group_message(10, "https://example.com/article")).await.unwrap();
+
+        assert_eq!(pocket.calls().len(), 1);
+        let methods: Vec<String> = telegram_calls(&telegram)
+            .into_iter()
+            .map(|(method, _)| method)
+            .collect();
+        assert_eq!(methods, vec!["sendMessage".to_string(), "setMessageReaction".to_string()]);
+    }
+}
@@ -32,13 +32,21 @@ use crate::{
         Application
     },
     telegram_client::{
-        TelegramUserId
+        TelegramChatId,
+        TelegramUpdateData
+    },
+    telegram_handlers::{
+        process_telegram_update,
+        refresh_dashboard
     },
     error::{
         TelegramBotError
     },
     model::{
         UserState
+    },
+    helpers::{
+        constant_time_eq
     }
 };
 
@@ -76,19 +84,21 @@ impl warp::reject::Reject for TelegramBotError {
 
 #[derive(Debug, Deserialize)]
 struct QueryParams{
-    user_id: TelegramUserId
+    /// Раньше состояние хранилось по пользователю, старые ссылки приходят с `user_id`
+    #[serde(alias = "user_id")]
+    chat_id: TelegramChatId
 }
 
 #[instrument(skip(app))]
 async fn callback_processor(app: Arc<Application>, params: QueryParams) -> Result<impl warp::Reply, Rejection> {
     let state = app
         .redis_client
-        .get_user_state(params.user_id)
+        .get_user_state(params.chat_id)
         .await
         .tap_err(|err|{ error!("User state receive error: {}", err); })?;
     
     match state {
-        UserState::AutorizationConfirmationWaiting{pocket_auth_code, telegram_message_id, telegram_user_id, ..} => {
+        UserState::AutorizationConfirmationWaiting{pocket_auth_code, telegram_message_id, telegram_chat_id, ..} => {
             // Заполучаем токен
             let token = app
                 .pocket_token_receiver
@@ -102,22 +112,24 @@ async fn callback_processor(app: Arc<Application>, params: QueryParams) -> Resul
                 Ok(token) =>{
                     // Обновляем состояние на авторизованое
                     app.redis_client
-                        .set_user_state(params.user_id, UserState::Authorized{
+                        .set_user_state(params.chat_id, UserState::Authorized{
                             pocket_api_token: token
                         }, None)
                         .await
                         .tap_err(|err|{ error!("User state update error: {}", err); })?;
+                    refresh_dashboard(&app, params.chat_id).await;
 
                     // Пишем сообщение пользователю про успешную авторизацию вместо ссылки
                     app
                         .telegram_client
-                        .update_message_text_by_id(telegram_user_id, telegram_message_id, "Authorization confirmed".to_string())
+                        .message_handle(telegram_chat_id, telegram_message_id)
+                        .edit_text("Authorization confirmed".to_string())
                         .await
                         .tap_err(|err|{ error!("User message send error: {}", err); })?;
 
                     // Редирект в телеграм
                     // TODO: Дубликат
-                    let url_str = app.telegram_bot_url.to_string();
+                    let url_str = app.telegram_bot.url.to_string();
                     let uri = warp::http::Uri::from_maybe_shared(url_str).unwrap();
                     return Ok(warp::redirect::see_other(uri));
                 },
@@ -129,20 +141,22 @@ async fn callback_processor(app: Arc<Application>, params: QueryParams) -> Resul
                             // Обновляем состояние на неавторизованное
                             app
                                 .redis_client
-                                .set_user_state(params.user_id, UserState::Unauthorized, None)
+                                .set_user_state(params.chat_id, UserState::Unauthorized, None)
                                 .await
                                 .tap_err(|err|{ error!("User state update error: {}", err); })?;
+                            refresh_dashboard(&app, params.chat_id).await;
 
                             // Пишем сообщение пользователю про НЕ успешную авторизацию вместо ссылки
                             app
                                 .telegram_client
-                                .update_message_text_by_id(telegram_user_id, telegram_message_id, "Authorization NOT confirmed".to_string())
+                                .message_handle(telegram_chat_id, telegram_message_id)
+                                .edit_text("Authorization NOT confirmed".to_string())
                                 .await
                                 .tap_err(|err|{ error!("User message send error: {}", err); })?;
 
                             // Редирект в телеграм
                             // TODO: Дубликат
-                            let url_str = app.telegram_bot_url.to_string();
+                            let url_str = app.telegram_bot.url.to_string();
                             let uri = warp::http::Uri::from_maybe_shared(url_str).unwrap();
                             return Ok(warp::redirect::see_other(uri));
                         },
@@ -161,6 +175,36 @@ async fn callback_processor(app: Arc<Application>, params: QueryParams) -> Resul
     Err(warp::reject::not_found())
 }
 
+/// Секрет вебхука не совпал, запрос отклоняется до разбора тела
+#[derive(Debug)]
+struct InvalidWebhookSecret;
+
+impl warp::reject::Reject for InvalidWebhookSecret {
+}
+
+/// Обновления принимаем только если секрет совпадает с тем, что мы передали в setWebhook.
+/// Проверка идет до чтения тела, чтобы посторонние запросы не заставляли разбирать JSON.
+#[instrument(skip(app, secret_token))]
+async fn check_webhook_secret(app: Arc<Application>, secret_token: Option<String>) -> Result<Arc<Application>, Rejection> {
+    let is_valid = match (app.telegram_webhook_secret.as_ref(), secret_token) {
+        (Some(expected), Some(received)) => constant_time_eq(expected.as_bytes(), received.as_bytes()),
+        _ => false
+    };
+    if !is_valid {
+        error!("Invalid webhook secret token");
+        return Err(warp::reject::custom(InvalidWebhookSecret));
+    }
+    Ok(app)
+}
+
+/// Прием обновлений от Telegram в режиме webhook
+#[instrument(skip(app, update))]
+async fn telegram_webhook_processor(app: Arc<Application>, update: TelegramUpdateData) -> Result<impl warp::Reply, Rejection> {
+    process_telegram_update(app, update).await;
+
+    Ok(warp::reply::with_status(warp::reply(), warp::http::StatusCode::OK))
+}
+
 #[instrument]
 async fn rejection_to_json(rejection: Rejection) -> Result<impl Reply, Rejection> {
     if let Some(err) = rejection.find::<TelegramBotError>(){
@@ -169,17 +213,38 @@ async fn rejection_to_json(rejection: Rejection) -> Result<impl Reply, Rejection
             "message": err.to_string()
         }));
         Ok(warp::reply::with_status(reply, warp::http::StatusCode::INTERNAL_SERVER_ERROR))
+    }else if rejection.find::<InvalidWebhookSecret>().is_some(){
+        let reply = warp::reply::json(&json!({
+            "code": warp::http::StatusCode::UNAUTHORIZED.as_u16(),
+            "message": "Invalid webhook secret token"
+        }));
+        Ok(warp::reply::with_status(reply, warp::http::StatusCode::UNAUTHORIZED))
     }else{
         Err(rejection)
     }
 }
 
 pub async fn run_server(app: Arc<Application>, port: u16) {
-    let routes = warp::get()
+    let app_filter = warp::any().map(move || { app.clone()});
+
+    let pocket_auth_callback = warp::get()
         .and(warp::path("pocket_auth_callback"))
-        .and(warp::any().map(move || { app.clone()}))
+        .and(app_filter.clone())
         .and(warp::query::<QueryParams>())
-        .and_then(callback_processor)
+        .and_then(callback_processor);
+
+    let telegram_webhook = warp::post()
+        .and(warp::path("telegram_webhook"))
+        .and(warp::path::end())
+        .and(app_filter)
+        .and(warp::header::optional::<String>("x-telegram-bot-api-secret-token"))
+        .and_then(check_webhook_secret)
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+        .and_then(telegram_webhook_processor);
+
+    let routes = pocket_auth_callback
+        .or(telegram_webhook)
         .recover(rejection_to_json);
 
     warp::serve(routes)